@@ -4,7 +4,11 @@ import { getWasmWorker } from "../worker.ts";
 import { useAsync } from "../hooks/useAsync.ts";
 import { TokenTextarea, Token } from "./TokenTextarea.tsx";
 import { DictionaryCharacterInfo, DictionaryPane } from "./DictionaryPane.tsx";
-import { ModeSwitcher, ModeValue } from "./ModeSwitcher.tsx";
+import {
+  ModeSwitcher,
+  ModeValue,
+  toDictionaryMode,
+} from "./ModeSwitcher.tsx";
 import { prettifyPinyin, prettifyExplanation } from "../utils.ts";
 import type { WordEntry } from "../worker.ts";
 
@@ -28,7 +32,7 @@ export const App: React.FunctionalComponent = () => {
   const [highlight, setHighlight] = useState<string>();
 
   const lookup = async (word: string, mode: ModeValue) =>
-    await wasmWorker.getWord(word, mode === "simplified");
+    await wasmWorker.getWord(word, toDictionaryMode(mode));
 
   const tokensTimeout = useRef<number | undefined>(undefined);
   const tokens = useAsync(async () => {
@@ -42,11 +46,17 @@ export const App: React.FunctionalComponent = () => {
 
     return tokens.map<Token>((token) => ({
       value: token.value,
-      unselectable: token.value.trim() === "" || !token.hasEntries,
+      unselectable: token.kind !== "chineseWord",
       pronunciation: async () => {
         const entries = [
-          ...(await wasmWorker.getWord(token.value, true)),
-          ...(await wasmWorker.getWord(token.value, false)),
+          ...(await wasmWorker.getWord(
+            token.value,
+            toDictionaryMode("simplified")
+          )),
+          ...(await wasmWorker.getWord(
+            token.value,
+            toDictionaryMode("traditional")
+          )),
         ];
 
         return [...new Set(entries.map((entry) => entry.pinyin))]
@@ -78,10 +88,12 @@ export const App: React.FunctionalComponent = () => {
       homophones:
         highlight == null
           ? []
-          : (await wasmWorker.getHomophones(highlight, mode === "simplified"))
+          : (
+              await wasmWorker.getHomophones(highlight, toDictionaryMode(mode))
+            )
               .map((entry) => ({
                 value: entry[0][mode],
-                highlight: entry[1],
+                highlight: entry[1] === "exact",
               }))
               .filter(
                 (entry, i, arr) => i === 0 || entry.value !== arr[i - 1].value
@@ -133,7 +145,7 @@ export const App: React.FunctionalComponent = () => {
           componentOf: (
             await wasmWorker.getCharactersIncludingComponent(
               character,
-              mode === "simplified"
+              toDictionaryMode(mode)
             )
           )
             .map((entry) => entry.character)
@@ -143,7 +155,7 @@ export const App: React.FunctionalComponent = () => {
             await wasmWorker.getWordsIncludingSubslice(
               character,
               200,
-              mode === "simplified"
+              toDictionaryMode(mode)
             )
           )
             .map((entry) => entry[mode])
@@ -161,7 +173,7 @@ export const App: React.FunctionalComponent = () => {
       : await wasmWorker.getSentencesIncludingWord(
           highlight,
           100,
-          mode === "simplified"
+          toDictionaryMode(mode)
         );
   }, [mode, highlight]);
 
@@ -171,7 +183,11 @@ export const App: React.FunctionalComponent = () => {
     return highlight == null
       ? []
       : (
-          await wasmWorker.getSimilarWords(highlight, 10, mode === "simplified")
+          await wasmWorker.getSimilarWords(
+            highlight,
+            10,
+            toDictionaryMode(mode)
+          )
         ).map((entry) => entry[0]);
   }, [highlight, mode]);
 
@@ -4,6 +4,7 @@ import { useResizeObserver } from "../hooks/useResizeObserver.ts";
 import { prettifyExplanation, prettifyPinyin } from "../utils.ts";
 import { getWasmWorker } from "../worker.ts";
 import { ModeContext } from "./App.tsx";
+import { toDictionaryMode } from "./ModeSwitcher.tsx";
 
 export interface DictionaryPopupLinkProps {
   word: string;
@@ -42,11 +43,16 @@ export const DictionaryPopupLink: React.FunctionComponent<
         hoverTimeout.current = setTimeout(async () => {
           const wasmWorker = getWasmWorker();
           const entries = await wasmWorker
-            .getWord(props.word, mode === "simplified")
+            .getWord(props.word, toDictionaryMode(mode))
             .then((entries) =>
               entries.length > 0
                 ? entries
-                : wasmWorker.getWord(props.word, mode !== "simplified")
+                : wasmWorker.getWord(
+                    props.word,
+                    toDictionaryMode(
+                      mode === "simplified" ? "traditional" : "simplified"
+                    )
+                  )
             );
           if (entries.length === 0) return;
 
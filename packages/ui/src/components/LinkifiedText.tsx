@@ -23,7 +23,7 @@ export const LinkifiedText: React.FunctionComponent<LinkifiedTextProps> = (
         <span style={{ visibility: "hidden" }}>{props.value}</span>
       ) : (
         tokens.value.map((token) => {
-          if (token.hasEntries) {
+          if (token.kind === "chineseWord") {
             return <DictionaryPopupLink word={token.value} />;
           } else if (
             props.handleSeparators &&
@@ -1,7 +1,13 @@
 import * as React from "preact";
+import type { DictionaryMode } from "../worker.ts";
 
 export type ModeValue = "simplified" | "traditional";
 
+export function toDictionaryMode(mode: ModeValue): DictionaryMode {
+  // Same values as the variants of `DictionaryMode`
+  return mode === "simplified" ? 1 : 2;
+}
+
 export interface ModeSwitcherProps {
   mode: ModeValue;
   onChange?: (evt: { mode: ModeValue }) => void;
@@ -1,12 +1,17 @@
 use rustc_hash::FxHashMap as HashMap;
 use serde::{Deserialize, Serialize};
 
+use crate::{
+  ParseErrorKind, ParseReport, SnapshotError, SnapshotKind, SnapshotReader,
+  SnapshotWriter,
+};
+
 pub const BINARY_DECOMPOSITION_TYPES: &[char] =
   &['⿰', '⿱', '⿴', '⿵', '⿶', '⿷', '⿸', '⿹', '⿺', '⿻'];
 
 pub const TRINARY_DECOMPOSITION_TYPES: &[char] = &['⿲', '⿳'];
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
 pub struct CharacterEtymology {
   #[serde(rename = "type")]
   pub ty: String,
@@ -35,17 +40,100 @@ pub struct CharacterDictionary {
 
 impl CharacterDictionary {
   pub fn new(data: &str) -> Self {
+    Self::parse_with_report(data).0
+  }
+
+  /// Parses data like [`CharacterDictionary::new`], but also reports
+  /// malformed lines instead of silently skipping them.
+  pub fn parse_with_report(data: &str) -> (Self, ParseReport) {
     let mut map = HashMap::with_capacity_and_hasher(16_384, Default::default());
+    let mut report = ParseReport::default();
 
-    for line in data.lines() {
-      if let Ok(mut entry) = serde_json::from_str::<CharacterEntry>(line) {
+    for (i, line) in data.lines().enumerate() {
+      if line.trim().is_empty() {
+        continue;
+      }
+
+      let result = serde_json::from_str::<CharacterEntry>(line)
+        .map(Some)
+        .map_err(|err| ParseErrorKind::InvalidJson(err.to_string().into()));
+
+      if let Some(mut entry) = report.record(i, result) {
         entry.strokes = entry.matches.len();
 
         map.insert(entry.character, entry);
       }
     }
 
-    Self { data: map }
+    (Self { data: map }, report)
+  }
+
+  pub fn to_snapshot(&self) -> Vec<u8> {
+    let mut writer = SnapshotWriter::new();
+
+    writer.write_len(self.data.len());
+
+    for entry in self.data.values() {
+      writer.write_char(entry.character);
+      writer.write_opt_str(entry.definition.as_deref());
+      writer.write_str(&entry.decomposition);
+
+      match &entry.etymology {
+        Some(etymology) => {
+          writer.write_u8(1);
+          writer.write_str(&etymology.ty);
+          writer.write_opt_str(etymology.hint.as_deref());
+          writer.write_opt_str(etymology.phonetic.as_deref());
+          writer.write_opt_str(etymology.semantic.as_deref());
+        }
+        None => writer.write_u8(0),
+      }
+
+      writer.write_len(entry.strokes);
+    }
+
+    writer.finish(SnapshotKind::Character)
+  }
+
+  pub fn from_snapshot(data: &[u8]) -> Result<Self, SnapshotError> {
+    let mut reader = SnapshotReader::new(data, SnapshotKind::Character)?;
+    let len = reader.read_len()?;
+    let mut map = HashMap::with_capacity_and_hasher(
+      len.min(data.len()),
+      Default::default(),
+    );
+
+    for _ in 0..len {
+      let character = reader.read_char()?;
+      let definition = reader.read_opt_str_ref()?.map(|x| x.to_string());
+      let decomposition = reader.read_str_ref()?.to_string();
+      let etymology = match reader.read_u8()? {
+        0 => None,
+        1 => Some(CharacterEtymology {
+          ty: reader.read_str_ref()?.to_string(),
+          hint: reader.read_opt_str_ref()?.map(|x| x.to_string()),
+          phonetic: reader.read_opt_str_ref()?.map(|x| x.to_string()),
+          semantic: reader.read_opt_str_ref()?.map(|x| x.to_string()),
+        }),
+        _ => return Err(SnapshotError::InvalidData),
+      };
+      let strokes = reader.read_len()?;
+
+      map.insert(
+        character,
+        CharacterEntry {
+          character,
+          definition,
+          decomposition,
+          etymology,
+          matches: vec![],
+          strokes,
+        },
+      );
+    }
+
+    reader.finish()?;
+    Ok(Self { data: map })
   }
 
   pub fn get(&self, character: char) -> Option<&CharacterEntry> {
@@ -70,12 +158,14 @@ impl CharacterDictionary {
     self
       .iter()
       .filter(move |entry| entry.character != component)
-      .filter(move |entry| {
-        self
-          .decompose(entry.character)
-          .iter_parts()
-          .any(|ch| ch == component)
-      })
+      .filter(move |entry| self.has_component(entry.character, component))
+  }
+
+  pub fn has_component(&self, character: char, component: char) -> bool {
+    self
+      .decompose(character)
+      .iter_parts()
+      .any(|ch| ch == component)
   }
 
   pub fn decompose(&self, character: char) -> CharacterDecomposition {
@@ -178,6 +268,7 @@ mod tests {
   use once_cell::sync::Lazy;
 
   use super::CharacterDictionary;
+  use crate::ParseErrorKind;
 
   static CHARACTER_DATA: Lazy<CharacterDictionary> = Lazy::new(|| {
     CharacterDictionary::new(include_str!("../../../data/dictionary.txt"))
@@ -187,4 +278,39 @@ mod tests {
   fn should_be_able_to_parse_dictionary_data() {
     let _ = *CHARACTER_DATA;
   }
+
+  #[test]
+  fn should_report_malformed_lines() {
+    let (dict, report) = CharacterDictionary::parse_with_report(
+      r#"{"character":"人","pinyin":[],"decomposition":"？","matches":[null]}
+
+{"character":"入","pinyin":[]
+{"character":"大","pinyin":[],"decomposition":"？","matches":[]}"#,
+    );
+
+    assert_eq!((report.entries, report.skipped), (2, 1));
+    assert_eq!(report.errors[0].line, 3);
+    assert!(matches!(
+      report.errors[0].kind,
+      ParseErrorKind::InvalidJson(_)
+    ));
+    assert!(dict.get('大').is_some());
+  }
+
+  #[test]
+  fn should_round_trip_snapshot() {
+    let dict =
+      CharacterDictionary::from_snapshot(&CHARACTER_DATA.to_snapshot())
+        .unwrap();
+
+    assert_eq!(dict.iter().count(), CHARACTER_DATA.iter().count());
+    assert_eq!(dict.decompose('好'), CHARACTER_DATA.decompose('好'));
+    assert_eq!(dict.stroke_count("好"), CHARACTER_DATA.stroke_count("好"));
+    assert_eq!(
+      dict.get('好').and_then(|entry| entry.etymology.as_ref()),
+      CHARACTER_DATA
+        .get('好')
+        .and_then(|entry| entry.etymology.as_ref())
+    );
+  }
 }
@@ -0,0 +1,221 @@
+use rustc_hash::FxHashMap as HashMap;
+use std::cmp::Reverse;
+
+use crate::{FrequencyDictionary, WordDictionary, WordEntry};
+
+/// Splits an English definition into its senses, each tokenized into
+/// lowercase words. Parentheticals, bracketed pinyin and classifier senses
+/// (`CL:...`) yield no words, but keep their place so that sense indices stay
+/// stable.
+fn tokenize_senses(english: &str) -> impl Iterator<Item = Vec<String>> + '_ {
+  english.split('/').map(|sense| {
+    let sense = sense.trim();
+
+    if sense.starts_with("CL:") {
+      return vec![];
+    }
+
+    let mut depth = 0usize;
+    let stripped = sense
+      .chars()
+      .filter(|&ch| match ch {
+        '(' | '[' => {
+          depth += 1;
+          false
+        }
+        ')' | ']' => {
+          depth = depth.saturating_sub(1);
+          false
+        }
+        _ => depth == 0,
+      })
+      .collect::<String>();
+
+    tokenize(&stripped)
+  })
+}
+
+fn tokenize(input: &str) -> Vec<String> {
+  input
+    .split(|ch: char| !ch.is_alphanumeric() && ch != '\'')
+    .map(|word| word.trim_matches('\''))
+    .filter(|word| !word.is_empty())
+    .map(|word| word.to_lowercase())
+    .collect()
+}
+
+/// An inverted index over the English definitions of a [`WordDictionary`]
+/// for reverse lookups.
+#[derive(Debug, Clone)]
+pub struct EnglishIndex {
+  entries: Vec<WordEntry>,
+  /// Maps each word to the entries and senses containing it, ordered by
+  /// entry and sense
+  postings: HashMap<Box<str>, Vec<(u32, u32)>>,
+}
+
+impl EnglishIndex {
+  pub fn new(word_dict: &WordDictionary) -> Self {
+    let entries = word_dict.iter().cloned().collect::<Vec<_>>();
+    let mut postings = HashMap::<Box<str>, Vec<(u32, u32)>>::default();
+
+    for (i, entry) in entries.iter().enumerate() {
+      for (j, words) in tokenize_senses(&entry.english).enumerate() {
+        for word in words {
+          let posting = (i as u32, j as u32);
+          let list = postings.entry(word.into()).or_default();
+
+          if list.last() != Some(&posting) {
+            list.push(posting);
+          }
+        }
+      }
+    }
+
+    Self { entries, postings }
+  }
+
+  /// Returns all entries with a sense containing every word of `query`.
+  /// Entries with a sense matching `query` exactly come first, then entries
+  /// with a single sense, then more frequent words.
+  pub fn search(
+    &self,
+    query: &str,
+    frequency_dict: &FrequencyDictionary,
+  ) -> Vec<&WordEntry> {
+    let words = tokenize(query);
+    let mut lists = words
+      .iter()
+      .map(|word| self.postings.get(word.as_str()).map(|list| &list[..]))
+      .collect::<Option<Vec<_>>>()
+      .unwrap_or_default();
+
+    lists.sort_by_key(|list| list.len());
+
+    let Some((first, rest)) = lists.split_first() else {
+      return vec![];
+    };
+
+    let hits = first
+      .iter()
+      .filter(|posting| {
+        rest.iter().all(|list| list.binary_search(posting).is_ok())
+      })
+      .copied();
+
+    // Keep the best sense of each entry
+
+    let mut ranked: Vec<(usize, bool, bool)> = vec![];
+
+    for (i, j) in hits {
+      let entry = &self.entries[i as usize];
+      let senses = tokenize_senses(&entry.english)
+        .filter(|words| !words.is_empty())
+        .count();
+      let exact = tokenize_senses(&entry.english).nth(j as usize).as_ref()
+        == Some(&words);
+
+      match ranked.last_mut() {
+        Some((last, last_exact, _)) if *last == i as usize => {
+          *last_exact |= exact;
+        }
+        _ => ranked.push((i as usize, exact, senses == 1)),
+      }
+    }
+
+    ranked.sort_by_cached_key(|&(i, exact, single_sense)| {
+      (
+        Reverse(exact),
+        Reverse(single_sense),
+        Reverse(frequency_dict.get(&self.entries[i].simplified).unwrap_or(0)),
+      )
+    });
+
+    ranked
+      .into_iter()
+      .map(|(i, _, _)| &self.entries[i])
+      .collect()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use once_cell::sync::Lazy;
+
+  use super::{tokenize_senses, EnglishIndex};
+  use crate::{FrequencyDictionary, WordDictionary};
+
+  static WORD_DATA: Lazy<WordDictionary> = Lazy::new(|| {
+    WordDictionary::new(
+      "傘 伞 [san3] /umbrella/parasol/CL:把[ba3]/
+雨傘 雨伞 [yu3 san3] /umbrella/CL:把[ba3]/
+陽傘 阳伞 [yang2 san3] /parasol/sunshade/umbrella/
+傘兵 伞兵 [san3 bing1] /paratrooper/umbrella troops (military)/
+走 走 [zou3] /to walk/to go/to run/to move (of vehicle)/to visit/to leave/
+散步 散步 [san4 bu4] /to take a walk/to go for a walk/
+步行 步行 [bu4 xing2] /to go on foot/to walk/pedestrian/
+",
+    )
+  });
+
+  static FREQUENCY_DATA: Lazy<FrequencyDictionary> = Lazy::new(|| {
+    FrequencyDictionary::new(
+      "\"Total word count: 33,546,516\"
+\"Context number: 6,243\"
+Word\tWCount\tW/million\tlogW\tW-CD\tW-CD%\tlogW-CD
+走\t73515\t2191.43\t4.8664\t5416\t86.75\t3.7337
+伞\t573\t17.08\t2.7582\t336\t5.38\t2.5263
+雨伞\t256\t7.63\t2.4082\t183\t2.93\t2.2625
+步行\t210\t6.26\t2.3222\t174\t2.79\t2.2405
+散步\t1086\t32.37\t3.0358\t713\t11.42\t2.8531
+阳伞\t12\t0.36\t1.0792\t11\t0.18\t1.0414",
+    )
+  });
+
+  static ENGLISH_DATA: Lazy<EnglishIndex> =
+    Lazy::new(|| EnglishIndex::new(&WORD_DATA));
+
+  fn search(query: &str) -> Vec<&str> {
+    ENGLISH_DATA
+      .search(query, &FREQUENCY_DATA)
+      .into_iter()
+      .map(|entry| &*entry.simplified)
+      .collect()
+  }
+
+  #[test]
+  fn should_ignore_parentheticals_and_classifiers() {
+    let senses = tokenize_senses(
+      "to move (of vehicle)/umbrella/CL:把[ba3]/Xi'an city, capital of 陝西省|陕西省[Shan3 xi1 sheng3]",
+    )
+    .collect::<Vec<_>>();
+
+    assert_eq!(
+      senses,
+      vec![
+        vec!["to", "move"],
+        vec!["umbrella"],
+        vec![],
+        vec!["xi'an", "city", "capital", "of", "陝西省", "陕西省"],
+      ]
+    );
+  }
+
+  #[test]
+  fn should_rank_exact_and_single_sense_matches_first() {
+    assert_eq!(search("umbrella"), vec!["雨伞", "伞", "阳伞", "伞兵"]);
+    assert_eq!(search("Umbrella!"), search("umbrella"));
+    assert_eq!(search("walk"), vec!["走", "散步", "步行"]);
+    assert_eq!(search("to walk"), vec!["走", "步行", "散步"]);
+  }
+
+  #[test]
+  fn should_require_all_words_in_one_sense() {
+    assert_eq!(search("walk run"), Vec::<&str>::new());
+    assert_eq!(search("walk go"), vec!["散步"]);
+    assert_eq!(search("for a walk"), vec!["散步"]);
+    assert_eq!(search("vehicle"), Vec::<&str>::new());
+    assert_eq!(search("ba3"), Vec::<&str>::new());
+    assert_eq!(search(""), Vec::<&str>::new());
+  }
+}
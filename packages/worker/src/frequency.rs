@@ -2,36 +2,143 @@ use std::{cmp::Ordering, sync::Arc};
 
 use rustc_hash::FxHashMap as HashMap;
 
+use crate::{
+  ParseErrorKind, ParseReport, SnapshotError, SnapshotKind, SnapshotReader,
+  SnapshotWriter,
+};
+
 #[derive(Debug, Clone)]
 pub struct FrequencyDictionary {
   data: HashMap<Arc<str>, f32>,
   max_log_frequency: f32,
+  total_log_frequency: f32,
 }
 
 impl FrequencyDictionary {
   pub fn new(data: &str) -> Self {
+    Self::parse_with_report(data).0
+  }
+
+  /// Parses data like [`FrequencyDictionary::new`], but also reports
+  /// malformed lines instead of silently skipping them.
+  pub fn parse_with_report(data: &str) -> (Self, ParseReport) {
     let mut max = 0.0_f32;
     let mut map =
       HashMap::with_capacity_and_hasher(131_072, Default::default());
+    let mut report = ParseReport::default();
 
-    for line in data.lines().skip(3) {
-      let mut tokens = line.split_ascii_whitespace();
+    // The first three lines hold totals and column names
+
+    for (i, line) in data.lines().enumerate().skip(3) {
+      if line.trim().is_empty() {
+        continue;
+      }
 
-      if let (Some(word), _, _, Some(log_frequency)) = (
-        tokens.next(),
-        tokens.next(),
-        tokens.next(),
-        tokens.next().and_then(|token| token.parse().ok()),
-      ) {
+      if let Some((word, log_frequency)) =
+        report.record(i, Self::parse_line(line))
+      {
         map.insert(Arc::from(word), log_frequency);
         max = max.max(log_frequency);
       }
     }
 
-    Self {
-      data: map,
-      max_log_frequency: max,
+    (
+      Self {
+        total_log_frequency: Self::total_log_frequency(&map),
+        data: map,
+        max_log_frequency: max,
+      },
+      report,
+    )
+  }
+
+  /// Parses a line of SUBTLEX-CH data, i.e. the word, its count, its count
+  /// per million and the logarithm of its count, followed by other columns.
+  fn parse_line(line: &str) -> Result<Option<(&str, f32)>, ParseErrorKind> {
+    let mut tokens = line.split_ascii_whitespace();
+    let word = tokens.next().ok_or(ParseErrorKind::MissingField("word"))?;
+    let log_frequency = tokens
+      .nth(2)
+      .ok_or(ParseErrorKind::MissingField("log frequency"))?;
+
+    log_frequency
+      .parse()
+      .map(|log_frequency| Some((word, log_frequency)))
+      .map_err(|_| ParseErrorKind::InvalidNumber(log_frequency.into()))
+  }
+
+  fn total_log_frequency(data: &HashMap<Arc<str>, f32>) -> f32 {
+    data
+      .values()
+      .map(|log_frequency| 10_f32.powf(*log_frequency))
+      .sum::<f32>()
+      .max(1.0)
+      .log10()
+  }
+
+  pub fn to_snapshot(&self) -> Vec<u8> {
+    let mut writer = SnapshotWriter::new();
+
+    writer.write_f32(self.max_log_frequency);
+    writer.write_len(self.data.len());
+
+    for (word, &log_frequency) in self.data.iter() {
+      writer.write_str(word);
+      writer.write_f32(log_frequency);
+    }
+
+    writer.finish(SnapshotKind::Frequency)
+  }
+
+  pub fn from_snapshot(data: &[u8]) -> Result<Self, SnapshotError> {
+    let mut reader = SnapshotReader::new(data, SnapshotKind::Frequency)?;
+    let max_log_frequency = reader.read_f32()?;
+    let len = reader.read_len()?;
+    let mut map = HashMap::with_capacity_and_hasher(
+      len.min(data.len()),
+      Default::default(),
+    );
+
+    for _ in 0..len {
+      map.insert(reader.read_str()?, reader.read_f32()?);
     }
+
+    reader.finish()?;
+    Ok(Self {
+      total_log_frequency: Self::total_log_frequency(&map),
+      data: map,
+      max_log_frequency,
+    })
+  }
+
+  /// Returns the base 10 logarithm of the probability of `word`. Words
+  /// without data are estimated from their characters, see
+  /// [`FrequencyDictionary::character_log_probability`].
+  pub fn log_probability(&self, word: &str) -> f32 {
+    self
+      .data
+      .get(word)
+      .map(|log_frequency| log_frequency - self.total_log_frequency)
+      .unwrap_or_else(|| self.character_log_probability(word))
+  }
+
+  /// Returns the base 10 logarithm of the probability of `word` as if its
+  /// characters occurred independently, treating each unknown character as
+  /// if it occurred once.
+  pub fn character_log_probability(&self, word: &str) -> f32 {
+    word
+      .chars()
+      .map(|ch| {
+        self.data.get(&*ch.to_string()).copied().unwrap_or(0.0)
+          - self.total_log_frequency
+      })
+      .sum()
+  }
+
+  /// Returns whether there is data for `word` itself, i.e. its probability
+  /// isn't estimated from its characters.
+  pub fn contains(&self, word: &str) -> bool {
+    self.data.contains_key(word)
   }
 
   pub fn get(&self, word: &str) -> Option<u8> {
@@ -56,6 +163,7 @@ mod tests {
   use once_cell::sync::Lazy;
 
   use super::FrequencyDictionary;
+  use crate::{ParseError, ParseErrorKind};
 
   static FREQUENCY_DATA: Lazy<FrequencyDictionary> = Lazy::new(|| {
     FrequencyDictionary::new(include_str!("../../../data/SUBTLEX-CH-CHR.txt"))
@@ -65,4 +173,43 @@ mod tests {
   fn should_be_able_to_parse_dictionary_data() {
     let _ = &*FREQUENCY_DATA;
   }
+
+  #[test]
+  fn should_report_malformed_lines() {
+    let (dict, report) = FrequencyDictionary::parse_with_report(
+      "\"Total word count: 33,546,516\"
+\"Context number: 6,243\"
+Word\tWCount\tW/million\tlogW\tW-CD\tW-CD%\tlogW-CD
+的\t1512022\t45072.34\t6.1796\t6243\t100\t3.7954
+我\t1118765
+是\t897617\t26757.57\tmany\t6243\t100\t3.7954
+",
+    );
+
+    assert!(dict.get("的").is_some());
+    assert_eq!((report.entries, report.skipped), (1, 2));
+    assert_eq!(
+      report.errors,
+      vec![
+        ParseError {
+          line: 5,
+          kind: ParseErrorKind::MissingField("log frequency"),
+        },
+        ParseError {
+          line: 6,
+          kind: ParseErrorKind::InvalidNumber("many".into()),
+        },
+      ]
+    );
+  }
+
+  #[test]
+  fn should_round_trip_snapshot() {
+    let dict =
+      FrequencyDictionary::from_snapshot(&FREQUENCY_DATA.to_snapshot())
+        .unwrap();
+
+    assert_eq!(dict.data, FREQUENCY_DATA.data);
+    assert_eq!(dict.get("的"), FREQUENCY_DATA.get("的"));
+  }
 }
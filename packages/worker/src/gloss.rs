@@ -0,0 +1,378 @@
+use serde::Serialize;
+use std::borrow::Cow;
+
+use crate::{
+  DictionaryType, Pinyin, PinyinFormat, TokenKind, WordDictionary, WordEntry,
+};
+
+/// A reference to another word in a CEDICT definition, written as
+/// `傳統|传统[chuan2 tong3]`, `卡拉OK[ka3 la1 O K]` or just `他`.
+#[derive(Debug, PartialEq, Eq, Clone, Serialize)]
+pub struct WordLink<'a> {
+  pub traditional: &'a str,
+  pub simplified: &'a str,
+  /// The pinyin as written in the definition, i.e. numbered
+  pub pinyin: Option<&'a str>,
+  /// The entry the link refers to, if it could be resolved
+  pub entry: Option<Cow<'a, WordEntry>>,
+}
+
+impl<'a> WordLink<'a> {
+  fn parse(word: &'a str, pinyin: Option<&'a str>) -> Self {
+    let (traditional, simplified) =
+      word.split_once('|').unwrap_or((word, word));
+
+    Self {
+      traditional,
+      simplified,
+      pinyin,
+      entry: None,
+    }
+  }
+
+  /// Returns the link with the pinyin of the resolved entry rendered in
+  /// `format`.
+  pub fn with_pinyin_format(mut self, format: PinyinFormat) -> Self {
+    self.format_pinyin(format);
+    self
+  }
+
+  fn format_pinyin(&mut self, format: PinyinFormat) {
+    self.entry = self.entry.take().map(|entry| match entry {
+      Cow::Borrowed(entry) => entry.with_pinyin_format(format),
+      Cow::Owned(entry) => {
+        Cow::Owned(entry.with_pinyin_format(format).into_owned())
+      }
+    });
+  }
+
+  /// Resolves the link to the entry with the same characters and, if given,
+  /// the same pinyin.
+  pub fn resolve(&mut self, word_dict: &'a WordDictionary) {
+    let pinyin = self.pinyin.map(Pinyin::parse);
+    let candidates = word_dict
+      .get(self.simplified, DictionaryType::Simplified)
+      .into_iter()
+      .chain(word_dict.get(self.traditional, DictionaryType::Traditional))
+      .flatten()
+      .filter(|entry| {
+        &*entry.simplified == self.simplified
+          && &*entry.traditional == self.traditional
+      });
+
+    self.entry = match &pinyin {
+      Some(pinyin) => candidates
+        .clone()
+        .find(|entry| entry.parse_pinyin().sounds_like(pinyin, true))
+        .or_else(|| candidates.clone().next()),
+      None => candidates.clone().next(),
+    }
+    .map(Cow::Borrowed);
+  }
+}
+
+/// A single sense of a CEDICT definition, i.e. the parts separated by `/`.
+#[derive(Debug, PartialEq, Eq, Clone, Serialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum Sense<'a> {
+  Gloss {
+    text: &'a str,
+  },
+  /// Measure words, written as `CL:個|个[ge4],位[wei4]`
+  Classifiers {
+    classifiers: Vec<WordLink<'a>>,
+  },
+  VariantOf {
+    text: &'a str,
+    links: Vec<WordLink<'a>>,
+  },
+  OldVariantOf {
+    text: &'a str,
+    links: Vec<WordLink<'a>>,
+  },
+  SeeAlso {
+    text: &'a str,
+    links: Vec<WordLink<'a>>,
+  },
+  AbbreviationOf {
+    text: &'a str,
+    links: Vec<WordLink<'a>>,
+  },
+  Surname {
+    text: &'a str,
+    name: &'a str,
+  },
+  /// A gloss with the scientific name of a species, e.g.
+  /// `Mongolian oak (Quercus mongolica)`
+  Taxon {
+    text: &'a str,
+    name: &'a str,
+  },
+}
+
+impl<'a> Sense<'a> {
+  pub fn parse(text: &'a str) -> Self {
+    let text = text.trim();
+
+    if let Some(rest) = text.strip_prefix("CL:") {
+      Sense::Classifiers {
+        classifiers: parse_links(rest),
+      }
+    } else if let Some(links) = parse_variant_links(text, "old variant of ") {
+      Sense::OldVariantOf { text, links }
+    } else if let Some(links) = parse_variant_links(text, "variant of ") {
+      Sense::VariantOf { text, links }
+    } else if let Some((_, rest)) = text.split_once("abbr. for ") {
+      Sense::AbbreviationOf {
+        text,
+        links: parse_links(rest),
+      }
+    } else if let Some(rest) = text
+      .strip_prefix("see also ")
+      .or_else(|| text.strip_prefix("see "))
+    {
+      Sense::SeeAlso {
+        text,
+        links: parse_links(rest),
+      }
+    } else if let Some(name) = text.strip_prefix("surname ") {
+      Sense::Surname { text, name }
+    } else if let Some(name) = parse_taxon(text) {
+      Sense::Taxon { text, name }
+    } else {
+      Sense::Gloss { text }
+    }
+  }
+
+  /// Returns the sense with the pinyin of all resolved entries rendered in
+  /// `format`.
+  pub fn with_pinyin_format(mut self, format: PinyinFormat) -> Self {
+    for link in self.links_mut() {
+      link.format_pinyin(format);
+    }
+
+    self
+  }
+
+  pub fn links(&self) -> &[WordLink<'a>] {
+    match self {
+      Sense::Classifiers { classifiers: links }
+      | Sense::VariantOf { links, .. }
+      | Sense::OldVariantOf { links, .. }
+      | Sense::SeeAlso { links, .. }
+      | Sense::AbbreviationOf { links, .. } => links,
+      _ => &[],
+    }
+  }
+
+  pub fn links_mut(&mut self) -> &mut [WordLink<'a>] {
+    match self {
+      Sense::Classifiers { classifiers: links }
+      | Sense::VariantOf { links, .. }
+      | Sense::OldVariantOf { links, .. }
+      | Sense::SeeAlso { links, .. }
+      | Sense::AbbreviationOf { links, .. } => links,
+      _ => &mut [],
+    }
+  }
+}
+
+/// Parses all senses of a CEDICT definition without resolving links.
+pub fn parse_senses(english: &str) -> Vec<Sense<'_>> {
+  english
+    .split('/')
+    .filter(|text| !text.trim().is_empty())
+    .map(Sense::parse)
+    .collect()
+}
+
+/// Finds all words containing Chinese characters with optional pinyin in
+/// brackets, skipping any other text such as `and`.
+fn parse_links(mut input: &str) -> Vec<WordLink<'_>> {
+  let mut links = vec![];
+
+  loop {
+    input = input.trim_start_matches(|ch: char| {
+      ch.is_whitespace() || matches!(ch, ',' | ';' | '.')
+    });
+
+    if input.is_empty() {
+      break;
+    }
+
+    let end = input
+      .find(|ch: char| {
+        ch.is_whitespace() || matches!(ch, ',' | ';' | '[' | '(' | ')')
+      })
+      .unwrap_or(input.len())
+      .max(input.chars().next().map(char::len_utf8).unwrap_or(0));
+    let word = &input[..end];
+    let mut pinyin = None;
+
+    input = &input[end..];
+
+    if let Some(rest) = input.strip_prefix('[') {
+      let close = rest.find(']').unwrap_or(rest.len());
+
+      pinyin = Some(&rest[..close]);
+      input = rest.get(close + 1..).unwrap_or("");
+    }
+
+    if word
+      .chars()
+      .any(|ch| TokenKind::of_char(ch) == TokenKind::UnknownHanzi)
+    {
+      links.push(WordLink::parse(word, pinyin));
+    }
+  }
+
+  links
+}
+
+/// Returns the words after `marker`, unless none of them are Chinese, as in
+/// `a variant of rock music`.
+fn parse_variant_links<'a>(
+  text: &'a str,
+  marker: &str,
+) -> Option<Vec<WordLink<'a>>> {
+  let (_, rest) = text.split_once(marker)?;
+  let links = parse_links(rest);
+
+  (!links.is_empty()).then_some(links)
+}
+
+/// Returns the scientific name in a trailing parenthetical such as
+/// `(Quercus mongolica)`, i.e. a capitalized genus followed by lowercase
+/// words.
+fn parse_taxon(text: &str) -> Option<&str> {
+  let name = text.strip_suffix(')')?.rsplit_once('(')?.1.trim();
+  let mut words = name.split(' ');
+  let genus = words.next()?;
+  let rest = words.collect::<Vec<_>>();
+
+  (genus.len() > 1
+    && genus.starts_with(|ch: char| ch.is_ascii_uppercase())
+    && genus[1..].chars().all(|ch| ch.is_ascii_lowercase())
+    && !rest.is_empty()
+    && rest.len() <= 3
+    && rest.iter().all(|word| {
+      !word.is_empty()
+        && word.chars().all(|ch| ch.is_ascii_lowercase() || ch == '.')
+    }))
+  .then_some(name)
+}
+
+#[cfg(test)]
+mod tests {
+  use once_cell::sync::Lazy;
+
+  use super::{parse_senses, Sense, WordLink};
+  use crate::{DictionaryType, WordDictionary};
+
+  static CEDICT_DATA: Lazy<WordDictionary> = Lazy::new(|| {
+    WordDictionary::new(include_str!(
+      "../../../data/cedict_1_0_ts_utf-8_mdbg.txt"
+    ))
+  });
+
+  fn link<'a>(
+    traditional: &'a str,
+    simplified: &'a str,
+    pinyin: Option<&'a str>,
+  ) -> WordLink<'a> {
+    WordLink {
+      traditional,
+      simplified,
+      pinyin,
+      entry: None,
+    }
+  }
+
+  #[test]
+  fn can_parse_senses() {
+    let senses = parse_senses(
+      "variant of 說|说[shuo1]/old variant of 乾[qian2]/see 卡拉OK[ka3 la1 O K]/abbr. for 北京大學|北京大学[Bei3 jing1 Da4 xue2]/surname Zhang/CL:個|个[ge4],隻|只[zhi1]/Mongolian oak (Quercus mongolica)/to open (Tw)/",
+    );
+
+    assert_eq!(
+      senses,
+      vec![
+        Sense::VariantOf {
+          text: "variant of 說|说[shuo1]",
+          links: vec![link("說", "说", Some("shuo1"))],
+        },
+        Sense::OldVariantOf {
+          text: "old variant of 乾[qian2]",
+          links: vec![link("乾", "乾", Some("qian2"))],
+        },
+        Sense::SeeAlso {
+          text: "see 卡拉OK[ka3 la1 O K]",
+          links: vec![link("卡拉OK", "卡拉OK", Some("ka3 la1 O K"))],
+        },
+        Sense::AbbreviationOf {
+          text: "abbr. for 北京大學|北京大学[Bei3 jing1 Da4 xue2]",
+          links: vec![link(
+            "北京大學",
+            "北京大学",
+            Some("Bei3 jing1 Da4 xue2")
+          )],
+        },
+        Sense::Surname {
+          text: "surname Zhang",
+          name: "Zhang",
+        },
+        Sense::Classifiers {
+          classifiers: vec![
+            link("個", "个", Some("ge4")),
+            link("隻", "只", Some("zhi1")),
+          ],
+        },
+        Sense::Taxon {
+          text: "Mongolian oak (Quercus mongolica)",
+          name: "Quercus mongolica",
+        },
+        Sense::Gloss {
+          text: "to open (Tw)",
+        },
+      ]
+    );
+
+    assert_eq!(
+      parse_senses("a variant of rock music"),
+      vec![Sense::Gloss {
+        text: "a variant of rock music",
+      }]
+    );
+  }
+
+  #[test]
+  fn can_parse_multiple_links() {
+    let senses =
+      parse_senses("see also 一點|一點[yi1 dian3] and 一些[yi1 xie1]");
+
+    assert_eq!(
+      senses[0].links(),
+      &[
+        link("一點", "一點", Some("yi1 dian3")),
+        link("一些", "一些", Some("yi1 xie1")),
+      ]
+    );
+  }
+
+  #[test]
+  fn can_resolve_links() {
+    let entry =
+      &CEDICT_DATA.get("中国", DictionaryType::Simplified).unwrap()[0];
+    let senses = CEDICT_DATA.get_senses(entry);
+
+    assert!(senses.iter().all(|sense| sense.links().is_empty()));
+
+    let entry = &CEDICT_DATA.get("床", DictionaryType::Simplified).unwrap()[0];
+    let senses = CEDICT_DATA.get_senses(entry);
+    let classifier = senses[3].links()[0].entry.as_deref().unwrap();
+
+    assert_eq!(&*classifier.simplified, "张");
+    assert_eq!(&*classifier.pinyin, "zhang1");
+    assert!(classifier.english.contains("classifier for flat objects"));
+  }
+}
@@ -0,0 +1,154 @@
+use std::fmt;
+
+use crate::pinyin::lookup;
+
+pub const JYUTPING_INITIALS: &[&str] = &[
+  "b", "p", "m", "f", "d", "t", "n", "l", "g", "k", "ng", "h", "gw", "kw", "w",
+  "z", "c", "s", "j",
+];
+
+/// All finals including the syllabic nasals `m` and `ng`.
+pub const JYUTPING_FINALS: &[&str] = &[
+  "aa", "aai", "aau", "aam", "aan", "aang", "aap", "aat", "aak", "a", "ai",
+  "au", "am", "an", "ang", "ap", "at", "ak", "e", "ei", "eu", "em", "en",
+  "eng", "ep", "et", "ek", "i", "iu", "im", "in", "ing", "ip", "it", "ik", "o",
+  "oi", "ou", "on", "ong", "ot", "ok", "oe", "oeng", "oek", "eoi", "eon",
+  "eot", "u", "ui", "un", "ung", "ut", "uk", "yu", "yun", "yut", "m", "ng",
+];
+
+/// A Cantonese syllable in Jyutping consisting of an initial, which is empty
+/// for syllables without one, a final and a tone from 1 to 6.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy)]
+pub struct JyutpingSyllable {
+  initial: &'static str,
+  final_: &'static str,
+  tone: u8,
+}
+
+impl JyutpingSyllable {
+  /// Parses a syllable such as `gwong2` or `ng5`, ignoring case.
+  pub fn parse(input: &str) -> Option<Self> {
+    let tone = input.chars().next_back()?.to_digit(10)? as u8;
+    let letters = input[..input.len() - 1].to_lowercase();
+
+    if !(1..=6).contains(&tone) {
+      return None;
+    }
+
+    // Syllabic nasals take precedence over the initials `m` and `ng`
+
+    let (initial, final_) = match lookup(JYUTPING_FINALS, &letters) {
+      Some(final_) if matches!(final_, "m" | "ng") => ("", final_),
+      _ => JYUTPING_INITIALS
+        .iter()
+        .copied()
+        .filter(|initial| letters.starts_with(initial))
+        .max_by_key(|initial| initial.len())
+        .and_then(|initial| {
+          Some((initial, lookup(JYUTPING_FINALS, &letters[initial.len()..])?))
+        })
+        .or_else(|| Some(("", lookup(JYUTPING_FINALS, &letters)?)))?,
+    };
+
+    Some(Self {
+      initial,
+      final_,
+      tone,
+    })
+  }
+
+  pub fn initial(&self) -> &'static str {
+    self.initial
+  }
+
+  pub fn final_(&self) -> &'static str {
+    self.final_
+  }
+
+  pub fn tone(&self) -> u8 {
+    self.tone
+  }
+
+  /// Returns whether both syllables sound alike apart from the tone and
+  /// sounds commonly merged in colloquial Cantonese, i.e. the initials
+  /// `n`/`l`, `ng`/none and `gw`/`g`, `kw`/`k` before `o` as well as the
+  /// codas `-ng`/`-n` and `-k`/`-t`.
+  pub fn is_confusable_with(&self, other: &JyutpingSyllable) -> bool {
+    fn initial(initial: &'static str, final_: &str) -> &'static str {
+      match initial {
+        "n" => "l",
+        "ng" => "",
+        "gw" if final_.starts_with('o') => "g",
+        "kw" if final_.starts_with('o') => "k",
+        _ => initial,
+      }
+    }
+
+    fn final_(final_: &str) -> String {
+      match final_ {
+        "m" | "ng" => final_.to_string(),
+        _ => {
+          if let Some(rest) = final_.strip_suffix("ng") {
+            format!("{rest}n")
+          } else if let Some(rest) = final_.strip_suffix('k') {
+            format!("{rest}t")
+          } else {
+            final_.to_string()
+          }
+        }
+      }
+    }
+
+    initial(self.initial, self.final_) == initial(other.initial, other.final_)
+      && final_(self.final_) == final_(other.final_)
+  }
+}
+
+impl fmt::Display for JyutpingSyllable {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "{}{}{}", self.initial, self.final_, self.tone)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::JyutpingSyllable;
+
+  fn split(input: &str) -> (&'static str, &'static str, u8) {
+    let syllable = JyutpingSyllable::parse(input).unwrap();
+
+    (syllable.initial(), syllable.final_(), syllable.tone())
+  }
+
+  #[test]
+  fn can_parse_syllables() {
+    assert_eq!(split("gwong2"), ("gw", "ong", 2));
+    assert_eq!(split("Ngo5"), ("ng", "o", 5));
+    assert_eq!(split("ng5"), ("", "ng", 5));
+    assert_eq!(split("m4"), ("", "m", 4));
+    assert_eq!(split("aa3"), ("", "aa", 3));
+    assert_eq!(split("jyut6"), ("j", "yut", 6));
+    assert_eq!(split("seoi2"), ("s", "eoi", 2));
+    assert_eq!(JyutpingSyllable::parse("hou7"), None);
+    assert_eq!(JyutpingSyllable::parse("hou"), None);
+    assert_eq!(JyutpingSyllable::parse("xyz1"), None);
+  }
+
+  #[test]
+  fn can_compare_confusable_syllables() {
+    let confusable = |a, b| {
+      JyutpingSyllable::parse(a)
+        .unwrap()
+        .is_confusable_with(&JyutpingSyllable::parse(b).unwrap())
+    };
+
+    assert!(confusable("nei5", "lei5"));
+    assert!(confusable("ngo5", "o5"));
+    assert!(confusable("gwong2", "gong2"));
+    assert!(confusable("hok6", "hot6"));
+    assert!(confusable("sang1", "san1"));
+    assert!(!confusable("gwan1", "gan1"));
+    assert!(!confusable("ng5", "m5"));
+    assert!(!confusable("sik1", "sap1"));
+  }
+}
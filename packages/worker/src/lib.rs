@@ -1,13 +1,36 @@
 mod character;
+mod english;
 mod frequency;
+mod gloss;
+mod jyutping;
+mod pattern;
+mod pinyin;
+mod report;
+mod rhyme;
+mod scanner;
+mod script;
 mod sentence;
+mod snapshot;
+mod source;
 mod thesaurus;
-mod trie;
+pub mod trie;
 pub mod wasm;
 mod word;
 
 pub use character::*;
+pub use english::*;
 pub use frequency::*;
+pub use gloss::*;
+pub use jyutping::*;
+pub use pattern::*;
+pub use pinyin::*;
+pub use report::*;
+pub use rhyme::*;
+pub use scanner::*;
+pub use script::*;
 pub use sentence::*;
+pub use snapshot::*;
+pub use source::*;
 pub use thesaurus::*;
+pub use trie::Trie;
 pub use word::*;
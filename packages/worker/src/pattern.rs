@@ -0,0 +1,214 @@
+use rustc_hash::FxHashSet as HashSet;
+
+use crate::trie::Trie;
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum PatternToken {
+  /// Matches exactly this character
+  Char(char),
+  /// Matches any single character, written as `?`
+  AnyChar,
+  /// Matches any sequence of characters, including the empty one, written
+  /// as `*`
+  AnySequence,
+  /// Matches a single character containing any of the given components,
+  /// written as `[氵木]`
+  Components(Vec<char>),
+}
+
+/// A word query such as `中?人`, `*子` or `[氵]?`.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct WordPattern {
+  tokens: Vec<PatternToken>,
+}
+
+impl WordPattern {
+  /// Parses a pattern, returns `None` if it is empty or contains an unclosed
+  /// or empty component class.
+  pub fn parse(pattern: &str) -> Option<Self> {
+    let mut chars = pattern.chars().filter(|ch| !ch.is_whitespace());
+    let mut tokens = vec![];
+
+    while let Some(ch) = chars.next() {
+      tokens.push(match ch {
+        '?' | '？' => PatternToken::AnyChar,
+        '*' | '＊' => {
+          if tokens.last() == Some(&PatternToken::AnySequence) {
+            continue;
+          }
+
+          PatternToken::AnySequence
+        }
+        '[' | '［' => {
+          let mut components = vec![];
+
+          loop {
+            match chars.next()? {
+              ']' | '］' => break,
+              component => components.push(component),
+            }
+          }
+
+          if components.is_empty() {
+            return None;
+          }
+
+          PatternToken::Components(components)
+        }
+        ch => PatternToken::Char(ch),
+      });
+    }
+
+    (!tokens.is_empty()).then_some(Self { tokens })
+  }
+
+  pub fn tokens(&self) -> &[PatternToken] {
+    &self.tokens
+  }
+
+  /// Returns all keys of `trie` matching this pattern with their values in
+  /// lexicographic order. `has_component` decides whether a character
+  /// contains a component.
+  pub fn find_in<'a, T>(
+    &self,
+    trie: &'a Trie<T>,
+    has_component: impl Fn(char, char) -> bool,
+  ) -> Vec<(String, &'a T)> {
+    struct Search<'a, 'b, T, F> {
+      has_component: F,
+      visited: HashSet<(*const Trie<T>, usize)>,
+      key: String,
+      result: Vec<(String, &'a T)>,
+      tokens: &'b [PatternToken],
+    }
+
+    impl<'a, 'b, T, F: Fn(char, char) -> bool> Search<'a, 'b, T, F> {
+      fn step(
+        &mut self,
+        trie: &'a Trie<T>,
+        ch: char,
+        value: Option<&'a T>,
+        index: usize,
+      ) {
+        self.key.push(ch);
+        self.visit(trie, value, index);
+        self.key.pop();
+      }
+
+      fn visit(
+        &mut self,
+        trie: &'a Trie<T>,
+        value: Option<&'a T>,
+        index: usize,
+      ) {
+        // The same subtrie with the same remaining pattern always yields the
+        // same keys, so there is no need to visit it twice
+
+        if !self.visited.insert((trie as *const _, index)) {
+          return;
+        }
+
+        let token = match self.tokens.get(index) {
+          Some(token) => token,
+          None => {
+            if let Some(value) = value {
+              self.result.push((self.key.clone(), value));
+            }
+
+            return;
+          }
+        };
+
+        match token {
+          &PatternToken::Char(ch) => {
+            if let Some((value, children)) = trie.child(&ch) {
+              self.step(children, ch, value, index + 1);
+            }
+          }
+          PatternToken::AnyChar => {
+            for (&ch, value, children) in trie.children() {
+              self.step(children, ch, value, index + 1);
+            }
+          }
+          PatternToken::Components(components) => {
+            for (&ch, value, children) in trie.children() {
+              if components.iter().any(|&component| {
+                ch == component || (self.has_component)(ch, component)
+              }) {
+                self.step(children, ch, value, index + 1);
+              }
+            }
+          }
+          PatternToken::AnySequence => {
+            self.visit(trie, value, index + 1);
+
+            for (&ch, value, children) in trie.children() {
+              self.step(children, ch, value, index);
+            }
+          }
+        }
+      }
+    }
+
+    let mut search = Search {
+      has_component,
+      visited: HashSet::default(),
+      key: String::new(),
+      result: vec![],
+      tokens: &self.tokens,
+    };
+
+    search.visit(trie, None, 0);
+    search.result.sort_by(|(x, _), (y, _)| x.cmp(y));
+    search.result
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::{PatternToken, WordPattern};
+  use crate::trie::Trie;
+
+  #[test]
+  fn should_parse_patterns() {
+    assert_eq!(
+      WordPattern::parse("中?[氵木]**人").unwrap().tokens(),
+      &[
+        PatternToken::Char('中'),
+        PatternToken::AnyChar,
+        PatternToken::Components(vec!['氵', '木']),
+        PatternToken::AnySequence,
+        PatternToken::Char('人'),
+      ]
+    );
+    assert_eq!(WordPattern::parse(""), None);
+    assert_eq!(WordPattern::parse("中[]"), None);
+    assert_eq!(WordPattern::parse("中[氵"), None);
+  }
+
+  #[test]
+  fn should_match_wildcards() {
+    let mut trie = Trie::new();
+
+    for word in ["中国", "中国人", "中年人", "中人", "子", "儿子", "桌子"]
+    {
+      trie.insert(word, ()).unwrap();
+    }
+
+    let find = |pattern| {
+      WordPattern::parse(pattern)
+        .unwrap()
+        .find_in(&trie, |ch, component| ch == '桌' && component == '木')
+        .into_iter()
+        .map(|(key, _)| key)
+        .collect::<Vec<_>>()
+    };
+
+    assert_eq!(find("中?人"), vec!["中国人", "中年人"]);
+    assert_eq!(find("中*人"), vec!["中人", "中国人", "中年人"]);
+    assert_eq!(find("*子"), vec!["儿子", "子", "桌子"]);
+    assert_eq!(find("[木]子"), vec!["桌子"]);
+    assert_eq!(find("*"), find("**"));
+    assert_eq!(find("*").len(), 7);
+  }
+}
@@ -0,0 +1,887 @@
+use std::fmt;
+use wasm_bindgen::prelude::wasm_bindgen;
+
+pub const INITIALS: &[&str] = &[
+  "b", "p", "m", "f", "d", "t", "n", "l", "g", "k", "h", "j", "q", "x", "zh",
+  "ch", "sh", "r", "z", "c", "s",
+];
+
+/// All finals in their phonological form, e.g. `iou` is spelled `iu` after
+/// consonants and `you` without an initial. `r` is the erhua suffix.
+pub const FINALS: &[&str] = &[
+  "a", "o", "e", "ê", "ai", "ei", "ao", "ou", "an", "en", "ang", "eng", "ong",
+  "er", "i", "ia", "io", "ie", "iao", "iou", "ian", "in", "iang", "ing",
+  "iong", "u", "ua", "uo", "uai", "uei", "uan", "uen", "uang", "ueng", "ü",
+  "üe", "üan", "ün", "m", "n", "ng", "r",
+];
+
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy)]
+pub enum Tone {
+  First = 1,
+  Second = 2,
+  Third = 3,
+  Fourth = 4,
+  Neutral = 5,
+}
+
+impl Tone {
+  pub fn from_number(number: u8) -> Option<Self> {
+    Some(match number {
+      1 => Tone::First,
+      2 => Tone::Second,
+      3 => Tone::Third,
+      4 => Tone::Fourth,
+      5 => Tone::Neutral,
+      _ => return None,
+    })
+  }
+
+  pub fn number(self) -> u8 {
+    self as u8
+  }
+}
+
+#[wasm_bindgen]
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum PinyinFormat {
+  /// Pinyin with tone numbers as in CEDICT, e.g. `zhong1 guo2`
+  Numbered = 1,
+  /// Pinyin with tone marks, e.g. `zhōngguó`
+  ToneMarks = 2,
+  /// Zhuyin, also known as Bopomofo, e.g. `ㄓㄨㄥ ㄍㄨㄛˊ`
+  Zhuyin = 3,
+  /// Wade–Giles with superscript tone numbers, e.g. `chung¹-kuo²`
+  WadeGiles = 4,
+  /// IPA with Chao tone letters, e.g. `ʈʂʊŋ˥ ku̯o˧˥`
+  Ipa = 5,
+}
+
+/// A Mandarin syllable consisting of an initial, which is empty for syllables
+/// without one, a final and a tone.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy)]
+pub struct Syllable {
+  initial: &'static str,
+  final_: &'static str,
+  tone: Tone,
+}
+
+impl Syllable {
+  /// Parses numbered pinyin such as `zhong1`, `lu:4`, `lv4`, `lü4` or `r5`,
+  /// ignoring case.
+  pub fn parse(input: &str) -> Option<Self> {
+    let tone = input.chars().next_back()?.to_digit(10)?;
+    let letters = &input[..input.len() - 1];
+    let tone = Tone::from_number(tone as u8)?;
+    let (initial, final_) = split_letters(letters)?;
+
+    Some(Self {
+      initial,
+      final_,
+      tone,
+    })
+  }
+
+  pub fn initial(&self) -> &'static str {
+    self.initial
+  }
+
+  pub fn final_(&self) -> &'static str {
+    self.final_
+  }
+
+  pub fn tone(&self) -> Tone {
+    self.tone
+  }
+
+  pub fn is_erhua(&self) -> bool {
+    self.final_ == "r"
+  }
+
+  /// Returns whether both syllables sound alike apart from the tone and
+  /// commonly confused sounds, i.e. the initials `z`/`zh`, `c`/`ch`, `s`/`sh`
+  /// and `n`/`l` as well as the nasal finals `-n`/`-ng`.
+  pub fn is_confusable_with(&self, other: &Syllable) -> bool {
+    fn initial(initial: &str) -> &str {
+      match initial {
+        "zh" => "z",
+        "ch" => "c",
+        "sh" => "s",
+        "l" => "n",
+        _ => initial,
+      }
+    }
+
+    fn final_(final_: &str) -> &str {
+      final_
+        .strip_suffix('g')
+        .filter(|final_| final_.ends_with('n'))
+        .unwrap_or(final_)
+    }
+
+    initial(self.initial) == initial(other.initial)
+      && final_(self.final_) == final_(other.final_)
+  }
+
+  /// Returns the syllable spelled in pinyin without tone, e.g. `you` for the
+  /// final `iou` without initial.
+  pub fn spelling(&self) -> String {
+    let initial = self.initial;
+    let final_ = self.final_;
+
+    if initial.is_empty() {
+      return match final_ {
+        "i" | "in" | "ing" => format!("y{final_}"),
+        "u" => "wu".to_string(),
+        "iou" => "you".to_string(),
+        _ if final_.starts_with('ü') => {
+          format!("yu{}", &final_['ü'.len_utf8()..])
+        }
+        _ if final_.starts_with('i') && final_.len() > 1 => {
+          format!("y{}", &final_[1..])
+        }
+        _ if final_.starts_with('u') && final_.len() > 1 => {
+          format!("w{}", &final_[1..])
+        }
+        _ => final_.to_string(),
+      };
+    }
+
+    let final_ = match final_ {
+      "iou" => "iu".to_string(),
+      "uei" => "ui".to_string(),
+      "uen" => "un".to_string(),
+      _ if matches!(initial, "j" | "q" | "x") => final_.replace('ü', "u"),
+      _ => final_.to_string(),
+    };
+
+    format!("{initial}{final_}")
+  }
+
+  pub fn format(&self, format: PinyinFormat) -> String {
+    match format {
+      PinyinFormat::Numbered => self.to_string(),
+      PinyinFormat::ToneMarks => self.to_tone_marks(),
+      PinyinFormat::Zhuyin => self.to_zhuyin(),
+      PinyinFormat::WadeGiles => self.to_wade_giles(),
+      PinyinFormat::Ipa => self.to_ipa(),
+    }
+  }
+
+  /// Places the tone mark on `a` or `e` if present, on the `o` of `ou`, and
+  /// otherwise on the last vowel, or on the first letter of syllables without
+  /// vowels such as `ng`.
+  pub fn to_tone_marks(&self) -> String {
+    const MARKS: &[(char, [char; 4])] = &[
+      ('a', ['ā', 'á', 'ǎ', 'à']),
+      ('e', ['ē', 'é', 'ě', 'è']),
+      ('i', ['ī', 'í', 'ǐ', 'ì']),
+      ('o', ['ō', 'ó', 'ǒ', 'ò']),
+      ('u', ['ū', 'ú', 'ǔ', 'ù']),
+      ('ü', ['ǖ', 'ǘ', 'ǚ', 'ǜ']),
+    ];
+    const COMBINING_MARKS: [char; 4] =
+      ['\u{304}', '\u{301}', '\u{30c}', '\u{300}'];
+
+    let spelling = self.spelling();
+    let tone = match self.tone {
+      Tone::Neutral => return spelling,
+      tone => tone.number() as usize - 1,
+    };
+    let is_vowel = |ch: char| "aeiouüê".contains(ch);
+    let index = spelling
+      .find(['a', 'e'])
+      .or_else(|| spelling.find("ou"))
+      .or_else(|| spelling.rfind(is_vowel))
+      .unwrap_or(0);
+
+    let mut result = String::with_capacity(spelling.len() + 2);
+
+    for (i, ch) in spelling.char_indices() {
+      if i != index {
+        result.push(ch);
+      } else if let Some((_, marks)) =
+        MARKS.iter().find(|(vowel, _)| *vowel == ch)
+      {
+        result.push(marks[tone]);
+      } else {
+        result.push(ch);
+        result.push(COMBINING_MARKS[tone]);
+      }
+    }
+
+    result
+  }
+
+  pub fn to_zhuyin(&self) -> String {
+    let initial = match self.initial {
+      "b" => "ㄅ",
+      "p" => "ㄆ",
+      "m" => "ㄇ",
+      "f" => "ㄈ",
+      "d" => "ㄉ",
+      "t" => "ㄊ",
+      "n" => "ㄋ",
+      "l" => "ㄌ",
+      "g" => "ㄍ",
+      "k" => "ㄎ",
+      "h" => "ㄏ",
+      "j" => "ㄐ",
+      "q" => "ㄑ",
+      "x" => "ㄒ",
+      "zh" => "ㄓ",
+      "ch" => "ㄔ",
+      "sh" => "ㄕ",
+      "r" => "ㄖ",
+      "z" => "ㄗ",
+      "c" => "ㄘ",
+      "s" => "ㄙ",
+      _ => "",
+    };
+    let final_ = match self.final_ {
+      // Syllables such as `zhi` and `si` consist of the initial only
+      "i"
+        if matches!(
+          self.initial,
+          "zh" | "ch" | "sh" | "r" | "z" | "c" | "s"
+        ) =>
+      {
+        ""
+      }
+      "a" => "ㄚ",
+      "o" => "ㄛ",
+      "e" => "ㄜ",
+      "ê" => "ㄝ",
+      "ai" => "ㄞ",
+      "ei" => "ㄟ",
+      "ao" => "ㄠ",
+      "ou" => "ㄡ",
+      "an" => "ㄢ",
+      "en" => "ㄣ",
+      "ang" => "ㄤ",
+      "eng" => "ㄥ",
+      "ong" => "ㄨㄥ",
+      "er" | "r" => "ㄦ",
+      "i" => "ㄧ",
+      "ia" => "ㄧㄚ",
+      "io" => "ㄧㄛ",
+      "ie" => "ㄧㄝ",
+      "iao" => "ㄧㄠ",
+      "iou" => "ㄧㄡ",
+      "ian" => "ㄧㄢ",
+      "in" => "ㄧㄣ",
+      "iang" => "ㄧㄤ",
+      "ing" => "ㄧㄥ",
+      "iong" => "ㄩㄥ",
+      "u" => "ㄨ",
+      "ua" => "ㄨㄚ",
+      "uo" => "ㄨㄛ",
+      "uai" => "ㄨㄞ",
+      "uei" => "ㄨㄟ",
+      "uan" => "ㄨㄢ",
+      "uen" => "ㄨㄣ",
+      "uang" => "ㄨㄤ",
+      "ueng" => "ㄨㄥ",
+      "ü" => "ㄩ",
+      "üe" => "ㄩㄝ",
+      "üan" => "ㄩㄢ",
+      "ün" => "ㄩㄣ",
+      "m" => "ㄇ",
+      "n" => "ㄋ",
+      "ng" => "ㄫ",
+      _ => "",
+    };
+
+    match self.tone {
+      _ if self.is_erhua() => final_.to_string(),
+      Tone::First => format!("{initial}{final_}"),
+      Tone::Second => format!("{initial}{final_}ˊ"),
+      Tone::Third => format!("{initial}{final_}ˇ"),
+      Tone::Fourth => format!("{initial}{final_}ˋ"),
+      Tone::Neutral => format!("˙{initial}{final_}"),
+    }
+  }
+
+  pub fn to_wade_giles(&self) -> String {
+    if self.is_erhua() {
+      return "rh".to_string();
+    }
+
+    let initial = match self.initial {
+      "b" => "p",
+      "p" => "p'",
+      "d" => "t",
+      "t" => "t'",
+      "g" => "k",
+      "k" => "k'",
+      "j" | "zh" => "ch",
+      "q" | "ch" => "ch'",
+      "x" => "hs",
+      "r" => "j",
+      "z" if self.final_ == "i" => "tz",
+      "c" if self.final_ == "i" => "tz'",
+      "s" if self.final_ == "i" => "ss",
+      "z" => "ts",
+      "c" => "ts'",
+      initial => initial,
+    };
+    let final_ = match (self.initial, self.final_) {
+      ("", "i") => "i",
+      ("", "iou") => "yu",
+      ("", "iong") => "yung",
+      ("", "ie") => "yeh",
+      ("", "ian") => "yen",
+      ("", "in" | "ing") => &self.spelling(),
+      ("", "u") => "wu",
+      ("", "uen") => "wên",
+      ("", "ueng") => "wêng",
+      ("", "ü") => "yü",
+      ("", "üe") => "yüeh",
+      ("", "üan") => "yüan",
+      ("", "ün") => "yün",
+      ("", final_) if final_.starts_with('i') => &format!("y{}", &final_[1..]),
+      ("", final_) if final_.starts_with('u') => &format!("w{}", &final_[1..]),
+      ("", "e") => "o",
+      ("z" | "c" | "s", "i") => "ŭ",
+      ("zh" | "ch" | "sh" | "r", "i") => "ih",
+      ("g" | "k" | "h", "e") => "o",
+      ("g" | "k" | "h" | "sh", "uo") => "uo",
+      (_, "uo") => "o",
+      ("g" | "k" | "h", "uei") => "uei",
+      (_, "uei") => "ui",
+      (_, "uen") => "un",
+      (_, "e") => "ê",
+      (_, "en") => "ên",
+      (_, "eng") => "êng",
+      (_, "er") => "êrh",
+      (_, "ê") => "eh",
+      (_, "ong") => "ung",
+      (_, "iong") => "iung",
+      (_, "ie") => "ieh",
+      (_, "iou") => "iu",
+      (_, "ian") => "ien",
+      (_, "üe") => "üeh",
+      (_, final_) => final_,
+    };
+    let tone = match self.tone {
+      Tone::First => "¹",
+      Tone::Second => "²",
+      Tone::Third => "³",
+      Tone::Fourth => "⁴",
+      Tone::Neutral => "",
+    };
+
+    format!("{initial}{final_}{tone}")
+  }
+
+  pub fn to_ipa(&self) -> String {
+    let initial = match self.initial {
+      "p" => "pʰ",
+      "b" => "p",
+      "t" => "tʰ",
+      "d" => "t",
+      "k" => "kʰ",
+      "g" => "k",
+      "h" => "x",
+      "j" => "tɕ",
+      "q" => "tɕʰ",
+      "x" => "ɕ",
+      "zh" => "ʈʂ",
+      "ch" => "ʈʂʰ",
+      "sh" => "ʂ",
+      "r" => "ʐ",
+      "z" => "ts",
+      "c" => "tsʰ",
+      initial => initial,
+    };
+    let final_ = match (self.initial, self.final_) {
+      ("zh" | "ch" | "sh" | "r", "i") => "ʐ̩",
+      ("z" | "c" | "s", "i") => "z̩",
+      (_, "a") => "a",
+      (_, "o") => "o",
+      (_, "e") => "ɤ",
+      (_, "ê") => "ɛ",
+      (_, "ai") => "ai̯",
+      (_, "ei") => "ei̯",
+      (_, "ao") => "ɑu̯",
+      (_, "ou") => "ou̯",
+      (_, "an") => "an",
+      (_, "en") => "ən",
+      (_, "ang") => "ɑŋ",
+      (_, "eng") => "ɤŋ",
+      (_, "ong") => "ʊŋ",
+      (_, "er") => "aɚ̯",
+      (_, "i") => "i",
+      (_, "ia") => "i̯a",
+      (_, "io") => "i̯ɔ",
+      (_, "ie") => "i̯ɛ",
+      (_, "iao") => "i̯ɑu̯",
+      (_, "iou") => "i̯ou̯",
+      (_, "ian") => "i̯ɛn",
+      (_, "in") => "in",
+      (_, "iang") => "i̯ɑŋ",
+      (_, "ing") => "iŋ",
+      (_, "iong") => "i̯ʊŋ",
+      (_, "u") => "u",
+      (_, "ua") => "u̯a",
+      (_, "uo") => "u̯o",
+      (_, "uai") => "u̯ai̯",
+      (_, "uei") => "u̯ei̯",
+      (_, "uan") => "u̯an",
+      (_, "uen") => "u̯ən",
+      (_, "uang") => "u̯ɑŋ",
+      (_, "ueng") => "u̯ɤŋ",
+      (_, "ü") => "y",
+      (_, "üe") => "ɥɛ",
+      (_, "üan") => "ɥɛn",
+      (_, "ün") => "yn",
+      (_, "m") => "m̩",
+      (_, "n") => "n̩",
+      (_, "ng") => "ŋ̍",
+      (_, "r") => "ɻ",
+      _ => "",
+    };
+    let tone = match self.tone {
+      _ if self.is_erhua() => "",
+      Tone::First => "˥",
+      Tone::Second => "˧˥",
+      Tone::Third => "˨˩˦",
+      Tone::Fourth => "˥˩",
+      Tone::Neutral => "",
+    };
+
+    format!("{initial}{final_}{tone}")
+  }
+}
+
+impl fmt::Display for Syllable {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "{}{}", self.spelling(), self.tone.number())
+  }
+}
+
+fn capitalize(value: &str) -> String {
+  let mut chars = value.chars();
+
+  chars
+    .next()
+    .map(|first| first.to_uppercase().chain(chars).collect())
+    .unwrap_or_default()
+}
+
+pub(crate) fn lookup(
+  table: &[&'static str],
+  value: &str,
+) -> Option<&'static str> {
+  table.iter().copied().find(|&item| item == value)
+}
+
+/// Splits lowercase pinyin letters without tone into initial and final.
+fn split_letters(letters: &str) -> Option<(&'static str, &'static str)> {
+  let letters = letters.to_lowercase().replace("u:", "ü").replace('v', "ü");
+
+  let final_ = |initial: &'static str, rest: &str| {
+    let rest = match rest {
+      _ if matches!(initial, "j" | "q" | "x") && rest.starts_with('u') => {
+        rest.replacen('u', "ü", 1)
+      }
+      "iu" => "iou".to_string(),
+      "ui" => "uei".to_string(),
+      "un" => "uen".to_string(),
+      _ => rest.to_string(),
+    };
+
+    lookup(FINALS, &rest).map(|final_| (initial, final_))
+  };
+
+  if let Some(rest) = letters.strip_prefix('y') {
+    let rest = match rest {
+      "i" | "in" | "ing" => rest.to_string(),
+      "ou" => "iou".to_string(),
+      _ if rest.starts_with('u') => rest.replacen('u', "ü", 1),
+      _ => format!("i{rest}"),
+    };
+
+    return lookup(FINALS, &rest).map(|final_| ("", final_));
+  }
+
+  if let Some(rest) = letters.strip_prefix('w') {
+    let rest = match rest {
+      "u" => rest.to_string(),
+      _ => format!("u{rest}"),
+    };
+
+    return lookup(FINALS, &rest).map(|final_| ("", final_));
+  }
+
+  INITIALS
+    .iter()
+    .filter(|initial| letters.starts_with(*initial))
+    .max_by_key(|initial| initial.len())
+    .and_then(|initial| final_(initial, &letters[initial.len()..]))
+    // Syllables without initial, e.g. `an`, `er`, or `ng` and `r`, which
+    // would otherwise be mistaken for initials
+    .or_else(|| lookup(FINALS, &letters).map(|final_| ("", final_)))
+}
+
+/// A single space-separated part of a pinyin reading.
+#[derive(Debug, PartialEq, Eq, Hash, Clone)]
+pub enum PinyinPart {
+  Syllable {
+    syllable: Syllable,
+    /// Whether the syllable is capitalized, e.g. in proper nouns
+    capitalized: bool,
+  },
+  /// Latin letters in words such as `卡拉OK`
+  Latin(Box<str>),
+  /// Anything else, e.g. `·` in transliterated names or `,` in idioms
+  Other(Box<str>),
+}
+
+impl PinyinPart {
+  pub fn parse(input: &str) -> Self {
+    match Syllable::parse(input) {
+      Some(syllable) => PinyinPart::Syllable {
+        syllable,
+        capitalized: input.starts_with(|ch: char| ch.is_uppercase()),
+      },
+      None if input.chars().all(|ch| ch.is_ascii_alphabetic()) => {
+        PinyinPart::Latin(input.into())
+      }
+      None => PinyinPart::Other(input.into()),
+    }
+  }
+
+  pub fn syllable(&self) -> Option<&Syllable> {
+    match self {
+      PinyinPart::Syllable { syllable, .. } => Some(syllable),
+      _ => None,
+    }
+  }
+
+  /// Compares parts ignoring capitalization and optionally tones.
+  pub fn sounds_like(&self, other: &PinyinPart, with_tones: bool) -> bool {
+    match (self, other) {
+      (
+        PinyinPart::Syllable { syllable: a, .. },
+        PinyinPart::Syllable { syllable: b, .. },
+      ) => {
+        a.initial == b.initial
+          && a.final_ == b.final_
+          && (!with_tones || a.tone == b.tone)
+      }
+      (PinyinPart::Latin(a), PinyinPart::Latin(b)) => a.eq_ignore_ascii_case(b),
+      (PinyinPart::Other(a), PinyinPart::Other(b)) => a == b,
+      _ => false,
+    }
+  }
+}
+
+impl fmt::Display for PinyinPart {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      PinyinPart::Syllable {
+        syllable,
+        capitalized: true,
+      } => write!(f, "{}", capitalize(&syllable.to_string())),
+      PinyinPart::Syllable { syllable, .. } => write!(f, "{syllable}"),
+      PinyinPart::Latin(value) | PinyinPart::Other(value) => {
+        write!(f, "{value}")
+      }
+    }
+  }
+}
+
+/// A parsed pinyin reading as found in CEDICT, e.g. `Zhong1 guo2`.
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Default)]
+pub struct Pinyin {
+  parts: Vec<PinyinPart>,
+}
+
+impl Pinyin {
+  pub fn parse(input: &str) -> Self {
+    Self {
+      parts: input.split_whitespace().map(PinyinPart::parse).collect(),
+    }
+  }
+
+  pub fn parts(&self) -> &[PinyinPart] {
+    &self.parts
+  }
+
+  pub fn syllables(&self) -> impl Iterator<Item = &Syllable> {
+    self.parts.iter().filter_map(|part| part.syllable())
+  }
+
+  /// Returns whether both readings consist of the same sounds, ignoring
+  /// capitalization and optionally tones.
+  pub fn sounds_like(&self, other: &Pinyin, with_tones: bool) -> bool {
+    self.parts.len() == other.parts.len()
+      && self
+        .parts
+        .iter()
+        .zip(other.parts.iter())
+        .all(|(a, b)| a.sounds_like(b, with_tones))
+  }
+
+  /// Renders the reading in the given format. Syllables of a word are joined
+  /// as usual for the format, e.g. with apostrophes before syllables starting
+  /// with `a`, `o` or `e` for pinyin with tone marks, and erhua is attached to
+  /// the preceding syllable.
+  pub fn format(&self, format: PinyinFormat) -> String {
+    let separator = match format {
+      PinyinFormat::Numbered | PinyinFormat::Zhuyin | PinyinFormat::Ipa => " ",
+      PinyinFormat::ToneMarks => "",
+      PinyinFormat::WadeGiles => "-",
+    };
+    let mut result = String::new();
+    let mut after_word = false;
+
+    for part in self.parts.iter() {
+      match part {
+        PinyinPart::Syllable {
+          syllable,
+          capitalized,
+        } => {
+          if after_word
+            && (format == PinyinFormat::Numbered || !syllable.is_erhua())
+          {
+            result.push_str(separator);
+
+            if format == PinyinFormat::ToneMarks
+              && syllable.spelling().starts_with(['a', 'o', 'e'])
+            {
+              result.push('\'');
+            }
+          }
+
+          let value = syllable.format(format);
+
+          if *capitalized {
+            result.push_str(&capitalize(&value));
+          } else {
+            result.push_str(&value);
+          }
+
+          after_word = true;
+        }
+        PinyinPart::Latin(value) => {
+          if after_word {
+            result.push_str(separator);
+          }
+
+          result.push_str(value);
+          after_word = true;
+        }
+        PinyinPart::Other(value) => {
+          if !result.is_empty() && &**value != "," {
+            result.push(' ');
+          }
+
+          result.push_str(value);
+          result.push(' ');
+          after_word = false;
+        }
+      }
+    }
+
+    result.truncate(result.trim_end().len());
+    result
+  }
+
+  /// Returns the normalized, lowercase parts, e.g. for use as keys.
+  pub fn keys(&self) -> Vec<String> {
+    self
+      .parts
+      .iter()
+      .map(|part| part.to_string().to_lowercase())
+      .collect()
+  }
+}
+
+impl fmt::Display for Pinyin {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    for (i, part) in self.parts.iter().enumerate() {
+      if i > 0 {
+        write!(f, " ")?;
+      }
+
+      write!(f, "{part}")?;
+    }
+
+    Ok(())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::{Pinyin, PinyinFormat, PinyinPart, Syllable, Tone};
+
+  fn split(input: &str) -> (&'static str, &'static str, Tone) {
+    let syllable = Syllable::parse(input).unwrap();
+
+    (syllable.initial(), syllable.final_(), syllable.tone())
+  }
+
+  #[test]
+  fn can_parse_syllables() {
+    assert_eq!(split("zhong1"), ("zh", "ong", Tone::First));
+    assert_eq!(split("Guo2"), ("g", "uo", Tone::Second));
+    assert_eq!(split("shi4"), ("sh", "i", Tone::Fourth));
+    assert_eq!(split("de5"), ("d", "e", Tone::Neutral));
+    assert_eq!(split("liu2"), ("l", "iou", Tone::Second));
+    assert_eq!(split("dui4"), ("d", "uei", Tone::Fourth));
+    assert_eq!(split("lun2"), ("l", "uen", Tone::Second));
+    assert_eq!(split("you3"), ("", "iou", Tone::Third));
+    assert_eq!(split("yi1"), ("", "i", Tone::First));
+    assert_eq!(split("ya1"), ("", "ia", Tone::First));
+    assert_eq!(split("wu3"), ("", "u", Tone::Third));
+    assert_eq!(split("wei4"), ("", "uei", Tone::Fourth));
+    assert_eq!(split("er2"), ("", "er", Tone::Second));
+    assert_eq!(split("ang4"), ("", "ang", Tone::Fourth));
+    assert_eq!(split("ng2"), ("", "ng", Tone::Second));
+    assert_eq!(split("hm5"), ("h", "m", Tone::Neutral));
+    assert_eq!(split("r5"), ("", "r", Tone::Neutral));
+
+    assert!(Syllable::parse("zhong").is_none());
+    assert!(Syllable::parse("zhong6").is_none());
+    assert!(Syllable::parse("xx5").is_none());
+    assert!(Syllable::parse("5").is_none());
+    assert!(Syllable::parse("").is_none());
+    assert!(Syllable::parse("lü").is_none());
+  }
+
+  #[test]
+  fn should_treat_u_umlaut_spellings_as_equal() {
+    for input in ["lu:4", "lv4", "lü4", "LU:4", "LÜ4"] {
+      assert_eq!(split(input), ("l", "ü", Tone::Fourth));
+    }
+
+    assert_eq!(split("nu:e4"), ("n", "üe", Tone::Fourth));
+    assert_eq!(split("ju2"), ("j", "ü", Tone::Second));
+    assert_eq!(split("xue2"), ("x", "üe", Tone::Second));
+    assert_eq!(split("yuan2"), ("", "üan", Tone::Second));
+    assert_ne!(split("lu4"), split("lu:4"));
+  }
+
+  #[test]
+  fn should_spell_syllables_in_standard_pinyin() {
+    for input in [
+      "zhong1", "liu2", "dui4", "lun2", "you3", "yi1", "ya1", "wu3", "wei4",
+      "er2", "lü4", "nüe4", "ju2", "xue2", "yuan2", "yun2", "ying1", "weng1",
+      "r5",
+    ] {
+      assert_eq!(Syllable::parse(input).unwrap().to_string(), input);
+    }
+
+    assert_eq!(Syllable::parse("lu:4").unwrap().to_string(), "lü4");
+  }
+
+  #[test]
+  fn can_parse_readings() {
+    let pinyin = Pinyin::parse("Zhong1 guo2");
+
+    assert_eq!(pinyin.syllables().count(), 2);
+    assert_eq!(pinyin.to_string(), "Zhong1 guo2");
+    assert_eq!(pinyin.keys(), vec!["zhong1", "guo2"]);
+
+    let pinyin = Pinyin::parse("ka3 la1 O K");
+
+    assert_eq!(pinyin.syllables().count(), 2);
+    assert_eq!(pinyin.parts()[2], PinyinPart::Latin("O".into()));
+
+    let pinyin = Pinyin::parse("yi1 dian3 r5");
+
+    assert!(pinyin.parts()[2].syllable().unwrap().is_erhua());
+
+    let pinyin = Pinyin::parse("A1 la1 ba1 ma3 · Ma3 ke4");
+
+    assert_eq!(pinyin.parts()[4], PinyinPart::Other("·".into()));
+  }
+
+  #[test]
+  fn can_compare_readings() {
+    let a = Pinyin::parse("Lu:4 shi1");
+    let b = Pinyin::parse("lv4 shi1");
+    let c = Pinyin::parse("lu:3 shi4");
+    let d = Pinyin::parse("lu4 shi1");
+
+    assert!(a.sounds_like(&b, true));
+    assert!(!a.sounds_like(&c, true));
+    assert!(a.sounds_like(&c, false));
+    assert!(!a.sounds_like(&d, false));
+    assert!(Pinyin::parse("ka3 la1 O K")
+      .sounds_like(&Pinyin::parse("ka3 la1 o k"), true));
+  }
+
+  #[test]
+  fn can_compare_confusable_syllables() {
+    let confusable = |a, b| {
+      Syllable::parse(a)
+        .unwrap()
+        .is_confusable_with(&Syllable::parse(b).unwrap())
+    };
+
+    assert!(confusable("zhang1", "zang4"));
+    assert!(confusable("chen2", "ceng2"));
+    assert!(confusable("shi4", "si4"));
+    assert!(confusable("nan2", "lang2"));
+    assert!(confusable("xin1", "xing2"));
+    assert!(confusable("huang2", "huan2"));
+    assert!(!confusable("zhang1", "jiang1"));
+    assert!(!confusable("fan4", "huan4"));
+    assert!(!confusable("dong1", "dou1"));
+  }
+
+  #[test]
+  fn can_render_tone_marks() {
+    let render = |input| Pinyin::parse(input).format(PinyinFormat::ToneMarks);
+
+    assert_eq!(render("zhong1 guo2"), "zhōngguó");
+    assert_eq!(render("Xi1 an1"), "Xī'ān");
+    assert_eq!(render("nu:3 er2"), "nǚ'ér");
+    assert_eq!(render("shui3"), "shuǐ");
+    assert_eq!(render("liu4"), "liù");
+    assert_eq!(render("gou4"), "gòu");
+    assert_eq!(render("lu:e4"), "lüè");
+    assert_eq!(render("xue2 sheng5"), "xuésheng");
+    assert_eq!(render("yi1 dian3 r5"), "yīdiǎnr");
+    assert_eq!(render("ng2"), "n\u{301}g");
+    assert_eq!(render("ka3 la1 O K"), "kǎlāOK");
+    assert_eq!(render("A1 la1 bo2 · Ma3 ke4"), "Ālābó · Mǎkè");
+    assert_eq!(
+      render("yi1 ma3 dang1 xian1 , wan4 ma3 ben1 teng2"),
+      "yīmǎdāngxiān, wànmǎbēnténg"
+    );
+  }
+
+  #[test]
+  fn can_render_zhuyin() {
+    let render = |input| Pinyin::parse(input).format(PinyinFormat::Zhuyin);
+
+    assert_eq!(render("zhong1 guo2"), "ㄓㄨㄥ ㄍㄨㄛˊ");
+    assert_eq!(render("shi4 de5"), "ㄕˋ ˙ㄉㄜ");
+    assert_eq!(render("lu:4 xiong2 yun2"), "ㄌㄩˋ ㄒㄩㄥˊ ㄩㄣˊ");
+    assert_eq!(render("yi1 dian3 r5"), "ㄧ ㄉㄧㄢˇㄦ");
+  }
+
+  #[test]
+  fn can_render_wade_giles() {
+    let render = |input| Pinyin::parse(input).format(PinyinFormat::WadeGiles);
+
+    assert_eq!(render("zhong1 guo2"), "chung¹-kuo²");
+    assert_eq!(render("Mao2 Ze2 dong1"), "Mao²-Tsê²-tung¹");
+    assert_eq!(render("zi4 ji3"), "tzŭ⁴-chi³");
+    assert_eq!(render("xue2 xi2"), "hsüeh²-hsi²");
+    assert_eq!(render("ri4 ben3"), "jih⁴-pên³");
+    assert_eq!(render("you3 yong4"), "yu³-yung⁴");
+  }
+
+  #[test]
+  fn can_render_ipa() {
+    let render = |input| Pinyin::parse(input).format(PinyinFormat::Ipa);
+
+    assert_eq!(render("zhong1 guo2"), "ʈʂʊŋ˥ ku̯o˧˥");
+    assert_eq!(render("lu:3 xing2"), "ly˨˩˦ ɕiŋ˧˥");
+    assert_eq!(render("si4 shi2"), "sz̩˥˩ ʂʐ̩˧˥");
+  }
+}
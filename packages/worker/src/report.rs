@@ -0,0 +1,102 @@
+use serde::Serialize;
+use std::fmt;
+
+#[derive(Debug, PartialEq, Eq, Clone, Serialize)]
+#[serde(tag = "type", content = "value", rename_all = "camelCase")]
+pub enum ParseErrorKind {
+  /// The line ends before the named field
+  MissingField(&'static str),
+  /// Pinyin isn't enclosed in brackets or is empty
+  InvalidPinyin,
+  /// Jyutping isn't enclosed in braces or is empty
+  InvalidJyutping,
+  /// The definition isn't enclosed in slashes
+  InvalidDefinition,
+  /// The definition has no senses
+  EmptyDefinition,
+  InvalidNumber(Box<str>),
+  InvalidJson(Box<str>),
+}
+
+impl fmt::Display for ParseErrorKind {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      ParseErrorKind::MissingField(field) => write!(f, "missing {field}"),
+      ParseErrorKind::InvalidPinyin => {
+        write!(f, "pinyin is not enclosed in brackets")
+      }
+      ParseErrorKind::InvalidJyutping => {
+        write!(f, "jyutping is not enclosed in braces")
+      }
+      ParseErrorKind::InvalidDefinition => {
+        write!(f, "definition is not enclosed in slashes")
+      }
+      ParseErrorKind::EmptyDefinition => write!(f, "definition is empty"),
+      ParseErrorKind::InvalidNumber(value) => {
+        write!(f, "invalid number \"{value}\"")
+      }
+      ParseErrorKind::InvalidJson(message) => {
+        write!(f, "invalid JSON: {message}")
+      }
+    }
+  }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct ParseError {
+  /// Line number, starting at 1
+  pub line: usize,
+  pub kind: ParseErrorKind,
+}
+
+impl fmt::Display for ParseError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "line {}: {}", self.line, self.kind)
+  }
+}
+
+impl std::error::Error for ParseError {}
+
+/// Summary of parsing dictionary data. Blank lines, comments and headers are
+/// not counted.
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct ParseReport {
+  /// Number of lines loaded as entries
+  pub entries: usize,
+  /// Number of lines not loaded, either because they're malformed or
+  /// deliberately ignored, e.g. duplicates
+  pub skipped: usize,
+  pub errors: Vec<ParseError>,
+}
+
+impl ParseReport {
+  pub fn is_ok(&self) -> bool {
+    self.errors.is_empty()
+  }
+
+  /// Records the outcome of parsing the line at `index`, starting at 0.
+  pub(crate) fn record<T>(
+    &mut self,
+    index: usize,
+    result: Result<Option<T>, ParseErrorKind>,
+  ) -> Option<T> {
+    match result {
+      Ok(Some(entry)) => {
+        self.entries += 1;
+        Some(entry)
+      }
+      Ok(None) => {
+        self.skipped += 1;
+        None
+      }
+      Err(kind) => {
+        self.skipped += 1;
+        self.errors.push(ParseError {
+          line: index + 1,
+          kind,
+        });
+        None
+      }
+    }
+  }
+}
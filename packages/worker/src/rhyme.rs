@@ -0,0 +1,233 @@
+use crate::{trie::Trie, Syllable, Tone, FINALS};
+
+/// A query for words by the shape of their reading, e.g. all two syllable
+/// words in tones 3-4 or all words rhyming with 光 `guang1`. Unset fields
+/// match anything.
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct PinyinQuery {
+  /// Final of the last syllable in its phonological form, see [`FINALS`]
+  pub final_: Option<&'static str>,
+  /// Tone of the last syllable
+  pub tone: Option<Tone>,
+  /// Tones of all syllables, `None` matching any tone
+  pub tones: Option<Vec<Option<Tone>>>,
+  /// Number of syllables
+  pub syllables: Option<usize>,
+}
+
+impl PinyinQuery {
+  /// Parses a rhyme such as `uang`, `-ian` or `uang1` into a final and an
+  /// optional tone. Besides the phonological forms, the spellings `iu`, `ui`
+  /// and `un` are accepted for `iou`, `uei` and `uen`, and `v` or `u:` for
+  /// `ü`.
+  pub fn parse_rhyme(input: &str) -> Option<(&'static str, Option<Tone>)> {
+    let input = input.trim().trim_start_matches('-').to_lowercase();
+    let (letters, tone) =
+      match input.strip_suffix(|ch: char| ch.is_ascii_digit()) {
+        Some(letters) => {
+          let tone = input[letters.len()..].parse().ok()?;
+
+          (letters, Some(Tone::from_number(tone)?))
+        }
+        None => (&*input, None),
+      };
+    let letters = letters.replace("u:", "ü").replace('v', "ü");
+    let final_ = match &*letters {
+      "iu" => "iou",
+      "ui" => "uei",
+      "un" => "uen",
+      letters => FINALS.iter().copied().find(|&final_| final_ == letters)?,
+    };
+
+    Some((final_, tone))
+  }
+
+  /// Parses a tone sequence such as `34`, `3-4` or `3 ?`, where `?` or `0`
+  /// matches any tone.
+  pub fn parse_tones(input: &str) -> Option<Vec<Option<Tone>>> {
+    let tones = input
+      .chars()
+      .filter(|ch| !ch.is_whitespace() && !matches!(ch, '-' | ','))
+      .map(|ch| match ch {
+        '?' | '？' | '0' => Some(None),
+        ch => Some(Some(Tone::from_number(ch.to_digit(10)? as u8)?)),
+      })
+      .collect::<Option<Vec<_>>>()?;
+
+    (!tones.is_empty()).then_some(tones)
+  }
+
+  /// Returns the number of syllables required by the query, if any.
+  fn len(&self) -> Option<usize> {
+    self.syllables.or(self.tones.as_ref().map(Vec::len))
+  }
+
+  /// Returns whether a reading consisting of `syllables` matches the query.
+  pub fn matches(&self, syllables: &[Syllable]) -> bool {
+    let Some(last) = syllables.last() else {
+      return false;
+    };
+
+    self.len().is_none_or(|len| syllables.len() == len)
+      && self.final_.is_none_or(|final_| last.final_() == final_)
+      && self.tone.is_none_or(|tone| last.tone() == tone)
+      && self.tones.as_ref().is_none_or(|tones| {
+        tones.len() == syllables.len()
+          && tones.iter().zip(syllables).all(|(tone, syllable)| {
+            tone.is_none_or(|tone| syllable.tone() == tone)
+          })
+      })
+  }
+
+  /// Returns the values of all keys of `trie` matching this query, where each
+  /// key element is a numbered syllable such as `zhong1`. Keys containing
+  /// anything but syllables never match.
+  pub fn find_in<'a, T>(&self, trie: &'a Trie<T, String>) -> Vec<&'a T> {
+    fn visit<'a, T>(
+      query: &PinyinQuery,
+      trie: &'a Trie<T, String>,
+      value: Option<&'a T>,
+      syllables: &mut Vec<Syllable>,
+      result: &mut Vec<&'a T>,
+    ) {
+      if let Some(value) = value {
+        if query.matches(syllables) {
+          result.push(value);
+        }
+      }
+
+      if query.len().is_some_and(|len| syllables.len() >= len) {
+        return;
+      }
+
+      let tone = query
+        .tones
+        .as_ref()
+        .and_then(|tones| tones.get(syllables.len()).copied().flatten());
+
+      for (key, value, children) in trie.children() {
+        let Some(syllable) = Syllable::parse(key) else {
+          continue;
+        };
+
+        if tone.is_some_and(|tone| syllable.tone() != tone) {
+          continue;
+        }
+
+        syllables.push(syllable);
+        visit(query, children, value, syllables, result);
+        syllables.pop();
+      }
+    }
+
+    let mut result = vec![];
+
+    visit(self, trie, None, &mut vec![], &mut result);
+    result
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::PinyinQuery;
+  use crate::{trie::Trie, Pinyin, Tone};
+
+  #[test]
+  fn can_parse_rhymes() {
+    assert_eq!(PinyinQuery::parse_rhyme("uang"), Some(("uang", None)));
+    assert_eq!(
+      PinyinQuery::parse_rhyme("-uang1"),
+      Some(("uang", Some(Tone::First)))
+    );
+    assert_eq!(PinyinQuery::parse_rhyme("iu"), Some(("iou", None)));
+    assert_eq!(PinyinQuery::parse_rhyme("lv3"), None);
+    assert_eq!(
+      PinyinQuery::parse_rhyme("u:e4"),
+      Some(("üe", Some(Tone::Fourth)))
+    );
+    assert_eq!(PinyinQuery::parse_rhyme("ian6"), None);
+    assert_eq!(PinyinQuery::parse_rhyme(""), None);
+  }
+
+  #[test]
+  fn can_parse_tones() {
+    assert_eq!(
+      PinyinQuery::parse_tones("3-4"),
+      Some(vec![Some(Tone::Third), Some(Tone::Fourth)])
+    );
+    assert_eq!(
+      PinyinQuery::parse_tones("1 ? 5"),
+      Some(vec![Some(Tone::First), None, Some(Tone::Neutral)])
+    );
+    assert_eq!(PinyinQuery::parse_tones("36"), None);
+    assert_eq!(PinyinQuery::parse_tones("-"), None);
+  }
+
+  #[test]
+  fn should_find_matching_readings() {
+    let mut trie = Trie::<&str, String>::new();
+
+    for pinyin in [
+      "guang1",
+      "huang1",
+      "guang3",
+      "yue4 guang1",
+      "yi3 hou4",
+      "xian1",
+      "xi1 an1",
+      "ka3 la1 O K",
+    ] {
+      trie.insert(&Pinyin::parse(pinyin).keys(), pinyin).unwrap();
+    }
+
+    let find = |query: PinyinQuery| {
+      let mut result = query
+        .find_in(&trie)
+        .into_iter()
+        .copied()
+        .collect::<Vec<_>>();
+
+      result.sort();
+      result
+    };
+
+    assert_eq!(
+      find(PinyinQuery {
+        final_: Some("uang"),
+        tone: Some(Tone::First),
+        ..Default::default()
+      }),
+      vec!["guang1", "huang1", "yue4 guang1"]
+    );
+    assert_eq!(
+      find(PinyinQuery {
+        final_: Some("uang"),
+        syllables: Some(1),
+        ..Default::default()
+      }),
+      vec!["guang1", "guang3", "huang1"]
+    );
+    assert_eq!(
+      find(PinyinQuery {
+        tones: PinyinQuery::parse_tones("3-4"),
+        ..Default::default()
+      }),
+      vec!["yi3 hou4"]
+    );
+    assert_eq!(
+      find(PinyinQuery {
+        tones: PinyinQuery::parse_tones("?1"),
+        final_: Some("an"),
+        ..Default::default()
+      }),
+      vec!["xi1 an1"]
+    );
+    assert_eq!(
+      find(PinyinQuery {
+        syllables: Some(4),
+        ..Default::default()
+      }),
+      Vec::<&str>::new()
+    );
+  }
+}
@@ -0,0 +1,252 @@
+use std::collections::VecDeque;
+
+use crate::{DictionaryType, Span, SpanUnit, WordDictionary};
+
+const NONE: u32 = u32::MAX;
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct WordMatch<'a> {
+  pub value: &'a str,
+  pub byte_span: Span,
+  pub char_span: Span,
+  pub utf16_span: Span,
+}
+
+impl WordMatch<'_> {
+  pub fn span(&self, unit: SpanUnit) -> Span {
+    match unit {
+      SpanUnit::Byte => self.byte_span,
+      SpanUnit::Char => self.char_span,
+      SpanUnit::Utf16 => self.utf16_span,
+    }
+  }
+}
+
+#[derive(Debug, Clone, Default)]
+struct ScannerNode {
+  children: Vec<(char, u32)>,
+  fail: u32,
+  output: u32,
+  depth: u32,
+  is_word: bool,
+}
+
+impl ScannerNode {
+  fn child(&self, ch: char) -> Option<u32> {
+    self
+      .children
+      .binary_search_by_key(&ch, |&(ch, _)| ch)
+      .ok()
+      .map(|i| self.children[i].1)
+  }
+}
+
+/// An Aho-Corasick automaton over all simplified and traditional words of a
+/// [`WordDictionary`], which finds every occurrence of every word in a text
+/// in a single pass.
+#[derive(Debug, Clone)]
+pub struct WordScanner {
+  nodes: Vec<ScannerNode>,
+}
+
+impl WordScanner {
+  pub fn new(word_dict: &WordDictionary) -> Self {
+    let mut nodes = vec![ScannerNode::default()];
+
+    // Build goto function, children are kept sorted for binary search
+
+    for ty in [DictionaryType::Simplified, DictionaryType::Traditional] {
+      for (word, _) in word_dict.iter_words_with_prefix("", None, ty) {
+        let mut node = 0;
+
+        for ch in word.chars() {
+          node = match nodes[node].child(ch) {
+            Some(child) => child as usize,
+            None => {
+              let child = nodes.len();
+              let depth = nodes[node].depth + 1;
+
+              nodes.push(ScannerNode {
+                depth,
+                ..Default::default()
+              });
+
+              let children = &mut nodes[node].children;
+              let index = children
+                .binary_search_by_key(&ch, |&(ch, _)| ch)
+                .unwrap_or_else(|i| i);
+
+              children.insert(index, (ch, child as u32));
+              child
+            }
+          };
+        }
+
+        nodes[node].is_word = true;
+      }
+    }
+
+    // Build failure and output links breadth first
+
+    nodes[0].output = NONE;
+
+    let mut queue = VecDeque::from([0]);
+
+    while let Some(node) = queue.pop_front() {
+      for i in 0..nodes[node].children.len() {
+        let (ch, child) = nodes[node].children[i];
+        let child = child as usize;
+        let fail = if node == 0 {
+          0
+        } else {
+          let mut fail = nodes[node].fail as usize;
+
+          loop {
+            if let Some(next) = nodes[fail].child(ch) {
+              break next as usize;
+            } else if fail == 0 {
+              break 0;
+            }
+
+            fail = nodes[fail].fail as usize;
+          }
+        };
+
+        nodes[child].fail = fail as u32;
+        nodes[child].output = if nodes[fail].is_word {
+          fail as u32
+        } else {
+          nodes[fail].output
+        };
+
+        queue.push_back(child);
+      }
+    }
+
+    Self { nodes }
+  }
+
+  /// Returns all dictionary words occurring in `input`, including
+  /// overlapping ones, ordered by start and then by length.
+  pub fn scan<'a>(&self, input: &'a str) -> Vec<WordMatch<'a>> {
+    // Offsets of each character boundary in bytes and UTF-16 code units
+
+    let mut offsets = vec![(0, 0)];
+
+    for ch in input.chars() {
+      let (byte, utf16) = offsets[offsets.len() - 1];
+
+      offsets.push((byte + ch.len_utf8(), utf16 + ch.len_utf16()));
+    }
+
+    let mut result = vec![];
+    let mut node = 0;
+
+    for (end, ch) in input.chars().enumerate() {
+      node = loop {
+        if let Some(next) = self.nodes[node].child(ch) {
+          break next as usize;
+        } else if node == 0 {
+          break 0;
+        }
+
+        node = self.nodes[node].fail as usize;
+      };
+
+      let mut output = if self.nodes[node].is_word {
+        node as u32
+      } else {
+        self.nodes[node].output
+      };
+
+      while output != NONE {
+        let found = &self.nodes[output as usize];
+        let (start, end) = (end + 1 - found.depth as usize, end + 1);
+        let (start_byte, start_utf16) = offsets[start];
+        let (end_byte, end_utf16) = offsets[end];
+
+        result.push(WordMatch {
+          value: &input[start_byte..end_byte],
+          byte_span: Span {
+            start: start_byte,
+            end: end_byte,
+          },
+          char_span: Span { start, end },
+          utf16_span: Span {
+            start: start_utf16,
+            end: end_utf16,
+          },
+        });
+
+        output = found.output;
+      }
+    }
+
+    result.sort_by_key(|word| (word.char_span.start, word.value.len()));
+    result
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use once_cell::sync::Lazy;
+
+  use super::WordScanner;
+  use crate::{Span, SpanUnit, WordDictionary};
+
+  static CEDICT_DATA: Lazy<WordDictionary> = Lazy::new(|| {
+    WordDictionary::new(include_str!(
+      "../../../data/cedict_1_0_ts_utf-8_mdbg.txt"
+    ))
+  });
+
+  static SCANNER: Lazy<WordScanner> =
+    Lazy::new(|| WordScanner::new(&CEDICT_DATA));
+
+  #[test]
+  fn should_find_overlapping_words() {
+    let words = SCANNER
+      .scan("我是中国人民")
+      .into_iter()
+      .map(|word| (word.value, word.char_span.start))
+      .collect::<Vec<_>>();
+
+    for word in [
+      ("我", 0),
+      ("是", 1),
+      ("中", 2),
+      ("中国", 2),
+      ("中国人", 2),
+      ("国", 3),
+      ("国人", 3),
+      ("人", 4),
+      ("人民", 4),
+      ("民", 5),
+    ] {
+      assert!(words.contains(&word), "{word:?} not found");
+    }
+  }
+
+  #[test]
+  fn should_find_traditional_words_and_skip_unknown_text() {
+    let words = SCANNER
+      .scan("David說中國話")
+      .into_iter()
+      .map(|word| (word.value, word.char_span.start))
+      .collect::<Vec<_>>();
+
+    assert!(words.contains(&("中國", 6)));
+    assert!(words.contains(&("中國話", 6)));
+    assert!(words.iter().all(|&(_, offset)| offset >= 5));
+  }
+
+  #[test]
+  fn should_report_spans_in_all_units() {
+    let words = SCANNER.scan("𠀀中国");
+    let word = words.iter().find(|word| word.value == "中国").unwrap();
+
+    assert_eq!(word.span(SpanUnit::Byte), Span { start: 4, end: 10 });
+    assert_eq!(word.span(SpanUnit::Char), Span { start: 1, end: 3 });
+    assert_eq!(word.span(SpanUnit::Utf16), Span { start: 2, end: 4 });
+  }
+}
@@ -0,0 +1,172 @@
+use serde::Serialize;
+use wasm_bindgen::prelude::wasm_bindgen;
+
+use crate::{DictionaryType, WordDictionary};
+
+/// Share of distinctive characters a script needs to not count as mixed
+const MAJORITY: f32 = 0.8;
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Script {
+  Simplified,
+  Traditional,
+  /// Both scripts occur without a clear majority
+  Mixed,
+  /// No character exists in only one script
+  Undetermined,
+}
+
+#[derive(Debug, PartialEq, Clone, Copy, Serialize)]
+pub struct ScriptDetection {
+  pub script: Script,
+  /// Between 0 and 1, grows with the share of characters supporting `script`
+  /// and with the number of distinctive characters
+  pub confidence: f32,
+  /// Number of characters that only exist in simplified form
+  pub simplified: usize,
+  /// Number of characters that only exist in traditional form
+  pub traditional: usize,
+}
+
+impl ScriptDetection {
+  /// Detects the script of `input` by counting characters that are only a
+  /// simplified or only a traditional form of the single character entries of
+  /// `word_dict`.
+  pub fn detect(input: &str, word_dict: &WordDictionary) -> Self {
+    let (mut simplified, mut traditional) = (0, 0);
+    let mut buf = [0; 4];
+
+    for ch in input.chars() {
+      let ch = &*ch.encode_utf8(&mut buf);
+
+      match (
+        word_dict.get(ch, DictionaryType::Simplified).is_some(),
+        word_dict.get(ch, DictionaryType::Traditional).is_some(),
+      ) {
+        (true, false) => simplified += 1,
+        (false, true) => traditional += 1,
+        _ => {}
+      }
+    }
+
+    let total = simplified + traditional;
+
+    if total == 0 {
+      return Self {
+        script: Script::Undetermined,
+        confidence: 0.0,
+        simplified,
+        traditional,
+      };
+    }
+
+    let majority = simplified.max(traditional) as f32 / total as f32;
+    let (script, share) = if majority < MAJORITY {
+      (Script::Mixed, 2.0 * (1.0 - majority))
+    } else if simplified >= traditional {
+      (Script::Simplified, majority)
+    } else {
+      (Script::Traditional, majority)
+    };
+
+    Self {
+      script,
+      confidence: share * total as f32 / (total + 1) as f32,
+      simplified,
+      traditional,
+    }
+  }
+
+  /// Returns the dictionary type to look up the text with, preferring
+  /// simplified if undecided.
+  pub fn dictionary_type(&self) -> DictionaryType {
+    if self.traditional > self.simplified {
+      DictionaryType::Traditional
+    } else {
+      DictionaryType::Simplified
+    }
+  }
+}
+
+#[wasm_bindgen]
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum DictionaryMode {
+  Simplified = 1,
+  Traditional = 2,
+  /// Uses the script detected in the input
+  Auto = 3,
+}
+
+impl DictionaryMode {
+  pub fn resolve(
+    self,
+    input: &str,
+    word_dict: &WordDictionary,
+  ) -> DictionaryType {
+    match self {
+      DictionaryMode::Simplified => DictionaryType::Simplified,
+      DictionaryMode::Traditional => DictionaryType::Traditional,
+      DictionaryMode::Auto => {
+        ScriptDetection::detect(input, word_dict).dictionary_type()
+      }
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use once_cell::sync::Lazy;
+
+  use super::{DictionaryMode, Script, ScriptDetection};
+  use crate::{DictionaryType, WordDictionary};
+
+  static CEDICT_DATA: Lazy<WordDictionary> = Lazy::new(|| {
+    WordDictionary::new(include_str!(
+      "../../../data/cedict_1_0_ts_utf-8_mdbg.txt"
+    ))
+  });
+
+  fn detect(input: &str) -> (Script, usize, usize) {
+    let detection = ScriptDetection::detect(input, &CEDICT_DATA);
+
+    (
+      detection.script,
+      detection.simplified,
+      detection.traditional,
+    )
+  }
+
+  #[test]
+  fn should_detect_script() {
+    assert_eq!(detect("我们是中国人"), (Script::Simplified, 2, 0));
+    assert_eq!(detect("我們是中國人"), (Script::Traditional, 0, 2));
+    assert_eq!(detect("我們是中国人"), (Script::Mixed, 1, 1));
+    assert_eq!(detect("你好"), (Script::Undetermined, 0, 0));
+    assert_eq!(detect("hello"), (Script::Undetermined, 0, 0));
+  }
+
+  #[test]
+  fn should_grow_confidence_with_evidence() {
+    let confidence =
+      |input| ScriptDetection::detect(input, &CEDICT_DATA).confidence;
+
+    assert_eq!(confidence("你好"), 0.0);
+    assert!(confidence("们") > 0.0);
+    assert!(confidence("们国") > confidence("们"));
+    assert!(confidence("们们国") > confidence("们们國"));
+  }
+
+  #[test]
+  fn can_resolve_auto_mode() {
+    let resolve = |input| DictionaryMode::Auto.resolve(input, &CEDICT_DATA);
+
+    assert_eq!(resolve("头发"), DictionaryType::Simplified);
+    assert_eq!(resolve("頭髮"), DictionaryType::Traditional);
+    assert_eq!(resolve("你好"), DictionaryType::Simplified);
+    assert_eq!(
+      DictionaryMode::Traditional.resolve("头发", &CEDICT_DATA),
+      DictionaryType::Traditional
+    );
+  }
+}
@@ -1,7 +1,10 @@
 use rustc_hash::FxHashMap as HashMap;
 use std::sync::Arc;
 
-use crate::{DictionaryType, WordDictionary};
+use crate::{
+  DictionaryType, ParseErrorKind, ParseReport, SnapshotError, SnapshotKind,
+  SnapshotReader, SnapshotWriter, WordDictionary,
+};
 
 #[derive(Debug, Clone)]
 pub struct SentenceEntry {
@@ -16,18 +19,32 @@ pub struct SentenceDictionary {
 
 impl SentenceDictionary {
   pub fn new(data: &str, word_dict: &WordDictionary) -> Self {
+    Self::parse_with_report(data, word_dict).0
+  }
+
+  /// Parses data like [`SentenceDictionary::new`], but also reports
+  /// malformed lines instead of silently skipping them.
+  pub fn parse_with_report(
+    data: &str,
+    word_dict: &WordDictionary,
+  ) -> (Self, ParseReport) {
     let mut map = HashMap::with_capacity_and_hasher(0, Default::default());
+    let mut report = ParseReport::default();
 
-    for line in data.lines() {
-      let mut fields = line.split("\t").skip(1);
+    for (i, line) in data.lines().enumerate() {
+      if line.trim().is_empty() {
+        continue;
+      }
 
-      if let (Some(sentence), _, Some(english)) =
-        (fields.next(), fields.next(), fields.next())
-      {
-        if map.contains_key(sentence) {
-          continue;
-        }
+      let mut fields = line.split("\t").skip(1);
+      let result = match (fields.next(), fields.next(), fields.next()) {
+        (None, _, _) => Err(ParseErrorKind::MissingField("sentence")),
+        (_, _, None) => Err(ParseErrorKind::MissingField("translation")),
+        (Some(sentence), _, Some(_)) if map.contains_key(sentence) => Ok(None),
+        (Some(sentence), _, Some(english)) => Ok(Some((sentence, english))),
+      };
 
+      if let Some((sentence, english)) = report.record(i, result) {
         let tokens = word_dict
           .tokenize(&sentence)
           .into_iter()
@@ -55,7 +72,48 @@ impl SentenceDictionary {
       }
     }
 
-    Self { data: map }
+    (Self { data: map }, report)
+  }
+
+  pub fn to_snapshot(&self) -> Vec<u8> {
+    let mut writer = SnapshotWriter::new();
+
+    writer.write_len(self.data.len());
+
+    for (sentence, entry) in self.data.iter() {
+      writer.write_str(sentence);
+      writer.write_str(&entry.english);
+      writer.write_len(entry.tokens.len());
+
+      for (simplified, traditional) in entry.tokens.iter() {
+        writer.write_str(simplified);
+        writer.write_str(traditional);
+      }
+    }
+
+    writer.finish(SnapshotKind::Sentence)
+  }
+
+  pub fn from_snapshot(data: &[u8]) -> Result<Self, SnapshotError> {
+    let mut reader = SnapshotReader::new(data, SnapshotKind::Sentence)?;
+    let len = reader.read_len()?;
+    let mut map = HashMap::with_capacity_and_hasher(
+      len.min(data.len()),
+      Default::default(),
+    );
+
+    for _ in 0..len {
+      let sentence = reader.read_str()?;
+      let english = reader.read_str()?;
+      let tokens = (0..reader.read_len()?)
+        .map(|_| Ok((reader.read_str()?, reader.read_str()?)))
+        .collect::<Result<_, _>>()?;
+
+      map.insert(sentence, SentenceEntry { tokens, english });
+    }
+
+    reader.finish()?;
+    Ok(Self { data: map })
   }
 
   pub fn iter_sentences_including_word<'a: 'b, 'b>(
@@ -95,7 +153,7 @@ mod tests {
   use once_cell::sync::Lazy;
 
   use super::SentenceDictionary;
-  use crate::WordDictionary;
+  use crate::{DictionaryType, WordDictionary};
 
   static CEDICT_DATA: Lazy<WordDictionary> = Lazy::new(|| {
     WordDictionary::new(include_str!(
@@ -114,4 +172,22 @@ mod tests {
   fn should_be_able_to_parse_dictionary_data() {
     let _ = &*SENTENCES_DATA;
   }
+
+  #[test]
+  fn should_round_trip_snapshot() {
+    let dict =
+      SentenceDictionary::from_snapshot(&SENTENCES_DATA.to_snapshot()).unwrap();
+    let sentences = |dict: &SentenceDictionary| {
+      let mut result = dict
+        .iter_sentences_including_word("中国", DictionaryType::Simplified)
+        .map(|(sentence, english)| (sentence, english.to_string()))
+        .collect::<Vec<_>>();
+
+      result.sort();
+      result
+    };
+
+    assert_eq!(dict.data.len(), SENTENCES_DATA.data.len());
+    assert_eq!(sentences(&dict), sentences(&SENTENCES_DATA));
+  }
 }
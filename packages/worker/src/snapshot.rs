@@ -0,0 +1,404 @@
+use rustc_hash::FxHashMap as HashMap;
+use std::{fmt, sync::Arc};
+use wasm_bindgen::prelude::wasm_bindgen;
+
+/// Identifies snapshot files, followed by the version and the kind.
+pub const SNAPSHOT_MAGIC: &[u8; 4] = b"ZLSN";
+
+/// Bump whenever the layout of any snapshot changes.
+pub const SNAPSHOT_VERSION: u32 = 5;
+
+#[wasm_bindgen]
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum SnapshotKind {
+  Word = 1,
+  Character = 2,
+  Frequency = 3,
+  Sentence = 4,
+  Thesaurus = 5,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum SnapshotError {
+  InvalidHeader,
+  UnsupportedVersion(u32),
+  WrongKind(u8),
+  UnexpectedEnd,
+  InvalidData,
+}
+
+impl fmt::Display for SnapshotError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      SnapshotError::InvalidHeader => write!(f, "not a snapshot"),
+      SnapshotError::UnsupportedVersion(version) => write!(
+        f,
+        "unsupported snapshot version {version}, expected {SNAPSHOT_VERSION}"
+      ),
+      SnapshotError::WrongKind(kind) => {
+        write!(f, "snapshot holds the wrong kind of dictionary ({kind})")
+      }
+      SnapshotError::UnexpectedEnd => write!(f, "snapshot is truncated"),
+      SnapshotError::InvalidData => write!(f, "snapshot is corrupted"),
+    }
+  }
+}
+
+impl std::error::Error for SnapshotError {}
+
+/// Writes a snapshot. All strings are interned into a single table which is
+/// placed in front of the body, the body itself only refers to string ids.
+///
+/// Layout: magic, version (u32), kind (u8), string count (u32), string end
+/// offsets (u32 each), string bytes, body. All numbers are little endian.
+#[derive(Debug, Default)]
+pub struct SnapshotWriter {
+  body: Vec<u8>,
+  string_ids: HashMap<Box<str>, u32>,
+  string_ends: Vec<u32>,
+  string_data: Vec<u8>,
+}
+
+impl SnapshotWriter {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  pub fn write_u8(&mut self, value: u8) {
+    self.body.push(value);
+  }
+
+  pub fn write_u32(&mut self, value: u32) {
+    self.body.extend_from_slice(&value.to_le_bytes());
+  }
+
+  pub fn write_f32(&mut self, value: f32) {
+    self.body.extend_from_slice(&value.to_le_bytes());
+  }
+
+  pub fn write_len(&mut self, len: usize) {
+    self.write_u32(len as u32);
+  }
+
+  pub fn write_char(&mut self, ch: char) {
+    self.write_u32(ch as u32);
+  }
+
+  /// Adds `value` to the string table unless it is already there and
+  /// returns its id without writing anything to the body.
+  pub fn intern(&mut self, value: &str) -> u32 {
+    match self.string_ids.get(value) {
+      Some(&id) => id,
+      None => {
+        let id = self.string_ends.len() as u32;
+
+        self.string_data.extend_from_slice(value.as_bytes());
+        self.string_ends.push(self.string_data.len() as u32);
+        self.string_ids.insert(value.into(), id);
+
+        id
+      }
+    }
+  }
+
+  pub fn write_str(&mut self, value: &str) {
+    let id = self.intern(value);
+
+    self.write_u32(id);
+  }
+
+  pub fn write_opt_str(&mut self, value: Option<&str>) {
+    match value {
+      Some(value) => {
+        self.write_u8(1);
+        self.write_str(value);
+      }
+      None => self.write_u8(0),
+    }
+  }
+
+  pub fn finish(self, kind: SnapshotKind) -> Vec<u8> {
+    let mut result = Vec::with_capacity(
+      13 + self.string_ends.len() * 4
+        + self.string_data.len()
+        + self.body.len(),
+    );
+
+    result.extend_from_slice(SNAPSHOT_MAGIC);
+    result.extend_from_slice(&SNAPSHOT_VERSION.to_le_bytes());
+    result.push(kind as u8);
+    result.extend_from_slice(&(self.string_ends.len() as u32).to_le_bytes());
+
+    for end in self.string_ends {
+      result.extend_from_slice(&end.to_le_bytes());
+    }
+
+    result.extend_from_slice(&self.string_data);
+    result.extend_from_slice(&self.body);
+    result
+  }
+}
+
+/// Reads a snapshot written by [`SnapshotWriter`] in place. Strings are
+/// borrowed from the buffer, or allocated on first use and shared afterwards
+/// when owned strings are needed.
+#[derive(Debug)]
+pub struct SnapshotReader<'a> {
+  data: &'a [u8],
+  string_ends: U32Array<'a>,
+  string_data: &'a str,
+  strings: Vec<Option<Arc<str>>>,
+}
+
+impl<'a> SnapshotReader<'a> {
+  pub fn new(
+    data: &'a [u8],
+    kind: SnapshotKind,
+  ) -> Result<Self, SnapshotError> {
+    let mut result = Self {
+      data,
+      string_ends: U32Array(&[]),
+      string_data: "",
+      strings: vec![],
+    };
+
+    if result.read_bytes(4).ok() != Some(&SNAPSHOT_MAGIC[..]) {
+      return Err(SnapshotError::InvalidHeader);
+    }
+
+    let version = result.read_u32()?;
+
+    if version != SNAPSHOT_VERSION {
+      return Err(SnapshotError::UnsupportedVersion(version));
+    }
+
+    let found_kind = result.read_u8()?;
+
+    if found_kind != kind as u8 {
+      return Err(SnapshotError::WrongKind(found_kind));
+    }
+
+    let count = result.read_len()?;
+    let string_ends = result.read_u32_array(count)?;
+    let string_data_len = match count {
+      0 => 0,
+      count => string_ends.get(count - 1) as usize,
+    };
+
+    result.string_data =
+      std::str::from_utf8(result.read_bytes(string_data_len)?)
+        .map_err(|_| SnapshotError::InvalidData)?;
+    result.string_ends = string_ends;
+    result.strings = vec![None; count];
+
+    Ok(result)
+  }
+
+  fn read_bytes(&mut self, len: usize) -> Result<&'a [u8], SnapshotError> {
+    if self.data.len() < len {
+      return Err(SnapshotError::UnexpectedEnd);
+    }
+
+    let (result, rest) = self.data.split_at(len);
+
+    self.data = rest;
+    Ok(result)
+  }
+
+  pub fn read_u8(&mut self) -> Result<u8, SnapshotError> {
+    Ok(self.read_bytes(1)?[0])
+  }
+
+  pub fn read_u32(&mut self) -> Result<u32, SnapshotError> {
+    let bytes = self.read_bytes(4)?;
+
+    Ok(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+  }
+
+  pub fn read_f32(&mut self) -> Result<f32, SnapshotError> {
+    self.read_u32().map(f32::from_bits)
+  }
+
+  pub fn read_len(&mut self) -> Result<usize, SnapshotError> {
+    self.read_u32().map(|len| len as usize)
+  }
+
+  pub fn read_char(&mut self) -> Result<char, SnapshotError> {
+    char::from_u32(self.read_u32()?).ok_or(SnapshotError::InvalidData)
+  }
+
+  /// Reads `len` numbers written by [`SnapshotWriter::write_u32`] as a view
+  /// into the buffer.
+  pub fn read_u32_array(
+    &mut self,
+    len: usize,
+  ) -> Result<U32Array<'a>, SnapshotError> {
+    self.read_bytes(len.saturating_mul(4)).map(U32Array)
+  }
+
+  /// Returns the interned string with the given `id` borrowed from the
+  /// buffer.
+  pub fn str_ref(&self, id: u32) -> Result<&'a str, SnapshotError> {
+    let id = id as usize;
+
+    if id >= self.string_ends.len() {
+      return Err(SnapshotError::InvalidData);
+    }
+
+    let start = match id {
+      0 => 0,
+      id => self.string_ends.get(id - 1) as usize,
+    };
+
+    self
+      .string_data
+      .get(start..self.string_ends.get(id) as usize)
+      .ok_or(SnapshotError::InvalidData)
+  }
+
+  /// Returns the interned string with the given `id`, which is allocated
+  /// once and shared by all later calls.
+  pub fn string(&mut self, id: u32) -> Result<Arc<str>, SnapshotError> {
+    if let Some(Some(value)) = self.strings.get(id as usize) {
+      return Ok(value.clone());
+    }
+
+    let value = Arc::<str>::from(self.str_ref(id)?);
+
+    self.strings[id as usize] = Some(value.clone());
+    Ok(value)
+  }
+
+  pub fn read_str(&mut self) -> Result<Arc<str>, SnapshotError> {
+    let id = self.read_u32()?;
+
+    self.string(id)
+  }
+
+  pub fn read_str_ref(&mut self) -> Result<&'a str, SnapshotError> {
+    let id = self.read_u32()?;
+
+    self.str_ref(id)
+  }
+
+  pub fn read_opt_str(&mut self) -> Result<Option<Arc<str>>, SnapshotError> {
+    match self.read_u8()? {
+      0 => Ok(None),
+      1 => self.read_str().map(Some),
+      _ => Err(SnapshotError::InvalidData),
+    }
+  }
+
+  pub fn read_opt_str_ref(&mut self) -> Result<Option<&'a str>, SnapshotError> {
+    match self.read_u8()? {
+      0 => Ok(None),
+      1 => self.read_str_ref().map(Some),
+      _ => Err(SnapshotError::InvalidData),
+    }
+  }
+
+  /// Makes sure the whole snapshot has been consumed.
+  pub fn finish(self) -> Result<(), SnapshotError> {
+    if self.data.is_empty() {
+      Ok(())
+    } else {
+      Err(SnapshotError::InvalidData)
+    }
+  }
+}
+
+/// Little endian numbers in a snapshot, read in place.
+#[derive(Debug, Clone, Copy)]
+pub struct U32Array<'a>(&'a [u8]);
+
+impl U32Array<'_> {
+  pub fn len(&self) -> usize {
+    self.0.len() / 4
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.0.is_empty()
+  }
+
+  /// Returns the number at `index`, which must be less than the length.
+  pub fn get(&self, index: usize) -> u32 {
+    let bytes = &self.0[index * 4..index * 4 + 4];
+
+    u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]])
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::{
+    SnapshotError, SnapshotKind, SnapshotReader, SnapshotWriter,
+    SNAPSHOT_VERSION,
+  };
+
+  #[test]
+  fn should_round_trip_values() {
+    let mut writer = SnapshotWriter::new();
+
+    writer.write_str("中国");
+    writer.write_u32(42);
+    writer.write_opt_str(None);
+    writer.write_opt_str(Some("中国"));
+    writer.write_char('𠀀');
+    writer.write_f32(1.5);
+
+    for number in [1, 2, 3] {
+      writer.write_u32(number);
+    }
+
+    writer.write_str("国");
+
+    let data = writer.finish(SnapshotKind::Word);
+    let mut reader = SnapshotReader::new(&data, SnapshotKind::Word).unwrap();
+    let first = reader.read_str().unwrap();
+
+    assert_eq!(&*first, "中国");
+    assert_eq!(reader.read_u32(), Ok(42));
+    assert_eq!(reader.read_opt_str(), Ok(None));
+    assert!(std::sync::Arc::ptr_eq(
+      &first,
+      &reader.read_opt_str().unwrap().unwrap()
+    ));
+    assert_eq!(reader.read_char(), Ok('𠀀'));
+    assert_eq!(reader.read_f32(), Ok(1.5));
+
+    let numbers = reader.read_u32_array(3).unwrap();
+
+    assert_eq!(numbers.len(), 3);
+    assert_eq!((numbers.get(0), numbers.get(2)), (1, 3));
+    assert_eq!(reader.read_str_ref(), Ok("国"));
+    assert_eq!(reader.str_ref(0), Ok("中国"));
+    assert_eq!(reader.str_ref(2), Err(SnapshotError::InvalidData));
+    assert_eq!(reader.finish(), Ok(()));
+  }
+
+  #[test]
+  fn should_check_header() {
+    let mut data = SnapshotWriter::new().finish(SnapshotKind::Word);
+
+    assert!(SnapshotReader::new(&data, SnapshotKind::Word).is_ok());
+    assert_eq!(
+      SnapshotReader::new(&data, SnapshotKind::Character).err(),
+      Some(SnapshotError::WrongKind(SnapshotKind::Word as u8))
+    );
+    assert_eq!(
+      SnapshotReader::new(&data[..6], SnapshotKind::Word).err(),
+      Some(SnapshotError::UnexpectedEnd)
+    );
+    assert_eq!(
+      SnapshotReader::new(b"hello", SnapshotKind::Word).err(),
+      Some(SnapshotError::InvalidHeader)
+    );
+
+    data[4..8].copy_from_slice(&(SNAPSHOT_VERSION + 1).to_le_bytes());
+
+    assert_eq!(
+      SnapshotReader::new(&data, SnapshotKind::Word).err(),
+      Some(SnapshotError::UnsupportedVersion(SNAPSHOT_VERSION + 1))
+    );
+  }
+}
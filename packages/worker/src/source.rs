@@ -0,0 +1,46 @@
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+/// Name of the source `WordDictionary::new` loads its data as.
+pub const BASE_SOURCE: &str = "cedict";
+
+/// How entries of a source combine with entries of lower priority sources.
+#[derive(
+  Debug, PartialEq, Eq, Clone, Copy, Default, Serialize, Deserialize,
+)]
+#[serde(rename_all = "camelCase")]
+pub enum OverlayMode {
+  /// Replaces all entries of lower priority sources for the same word
+  Shadow,
+  /// Adds entries next to those of lower priority sources, only replacing
+  /// entries with the same reading
+  #[default]
+  Append,
+}
+
+/// A source of CEDICT formatted entries, e.g. CEDICT itself or a user
+/// glossary overlaid on top of it.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct DictionarySource {
+  /// Identifies the source, every entry refers to its source by name
+  pub name: Arc<str>,
+  /// Sources of higher priority win over sources of lower priority. If the
+  /// priority is equal, the source added last wins.
+  pub priority: i32,
+  pub mode: OverlayMode,
+}
+
+impl DictionarySource {
+  pub fn new(name: &str, priority: i32, mode: OverlayMode) -> Self {
+    Self {
+      name: name.into(),
+      priority,
+      mode,
+    }
+  }
+
+  /// Returns the source of the base dictionary, which has priority 0.
+  pub fn base() -> Self {
+    Self::new(BASE_SOURCE, 0, OverlayMode::Append)
+  }
+}
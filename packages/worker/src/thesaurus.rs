@@ -1,7 +1,10 @@
 use rustc_hash::{FxHashMap as HashMap, FxHashSet as HashSet};
 use std::cmp::Ordering;
 
-use crate::{DictionaryType, WordDictionary};
+use crate::{
+  DictionaryType, Sense, SnapshotError, SnapshotKind, SnapshotReader,
+  SnapshotWriter, WordDictionary,
+};
 
 #[derive(Debug, Clone)]
 pub struct ThesaurusDictionary {
@@ -50,24 +53,71 @@ impl ThesaurusDictionary {
     }
   }
 
+  pub fn to_snapshot(&self) -> Vec<u8> {
+    let mut writer = SnapshotWriter::new();
+
+    for map in [&self.simplified, &self.traditional] {
+      writer.write_len(map.len());
+
+      for (word, keywords) in map.iter() {
+        writer.write_str(word);
+        writer.write_len(keywords.len());
+
+        for keyword in keywords.iter() {
+          writer.write_str(keyword);
+        }
+      }
+    }
+
+    writer.finish(SnapshotKind::Thesaurus)
+  }
+
+  pub fn from_snapshot(data: &[u8]) -> Result<Self, SnapshotError> {
+    let mut reader = SnapshotReader::new(data, SnapshotKind::Thesaurus)?;
+    let read_map = |reader: &mut SnapshotReader| {
+      (0..reader.read_len()?)
+        .map(|_| {
+          let word = reader.read_str_ref()?.to_string();
+          let keywords = (0..reader.read_len()?)
+            .map(|_| Ok(reader.read_str_ref()?.to_string()))
+            .collect::<Result<_, _>>()?;
+
+          Ok((word, keywords))
+        })
+        .collect::<Result<_, SnapshotError>>()
+    };
+
+    let result = Self {
+      simplified: read_map(&mut reader)?,
+      traditional: read_map(&mut reader)?,
+    };
+
+    reader.finish()?;
+    Ok(result)
+  }
+
   fn extract_keywords(
     input: &str,
     word_dict: &WordDictionary,
   ) -> HashSet<String> {
-    let meanings = input.split("/").filter(|meaning| {
-      !meaning.contains("classifier for")
-        && !meaning.contains("variant of")
-        && !meaning.contains("surname ")
-        && !meaning.contains("CL:")
-    });
+    let meanings =
+      input
+        .split("/")
+        .filter_map(|meaning| match Sense::parse(meaning) {
+          Sense::Classifiers { .. }
+          | Sense::VariantOf { .. }
+          | Sense::OldVariantOf { .. }
+          | Sense::Surname { .. } => None,
+          Sense::Gloss { text } if text.contains("classifier for") => None,
+          sense => Some((meaning, sense)),
+        });
 
-    let keywords = meanings.flat_map(|meaning| {
-      if meaning.contains("abbr. for")
+    let keywords = meanings.flat_map(|(meaning, sense)| {
+      if matches!(sense, Sense::AbbreviationOf { .. } | Sense::SeeAlso { .. })
         || meaning.contains("also written")
         || meaning.contains("also called")
         || meaning.contains("also named")
         || meaning.contains("also pr.")
-        || meaning.starts_with("see ")
       {
         word_dict
           .tokenize(meaning)
@@ -185,4 +235,14 @@ mod tests {
   fn should_be_able_to_parse_thesaurus_data() {
     let _ = &*THESAURUS_DICT;
   }
+
+  #[test]
+  fn should_round_trip_snapshot() {
+    let dict =
+      ThesaurusDictionary::from_snapshot(&THESAURUS_DICT.to_snapshot())
+        .unwrap();
+
+    assert_eq!(dict.simplified, THESAURUS_DICT.simplified);
+    assert_eq!(dict.traditional, THESAURUS_DICT.traditional);
+  }
 }
@@ -1,93 +1,266 @@
-use rustc_hash::FxHashMap as HashMap;
-use std::iter::Peekable;
+use std::{
+  collections::{btree_map, BTreeMap},
+  iter::{Cloned, Enumerate, Map, Peekable},
+  slice,
+  str::CharIndices,
+  sync::Arc,
+};
 
+use crate::snapshot::{SnapshotError, SnapshotReader, SnapshotWriter};
+
+type Node<T, K> = (Option<T>, Box<Trie<T, K>>);
+
+/// An element a [`Trie`] key is made of.
+pub trait KeyElement: Ord + Clone {
+  /// The owned form of a whole key, as it gets yielded by iterators.
+  type Key: FromIterator<Self>;
+}
+
+impl KeyElement for char {
+  type Key = String;
+}
+
+macro_rules! impl_key_element {
+  ($($ty:ty),*) => {
+    $(
+      impl KeyElement for $ty {
+        type Key = Vec<$ty>;
+      }
+    )*
+  };
+}
+
+impl_key_element!(u8, u16, u32, String, Arc<str>);
+
+/// A key element that can be stored in a snapshot as a single number.
+pub trait SnapshotElement: Sized {
+  fn to_snapshot(&self, writer: &mut SnapshotWriter) -> u32;
+
+  fn from_snapshot(
+    value: u32,
+    reader: &mut SnapshotReader,
+  ) -> Result<Self, SnapshotError>;
+}
+
+impl SnapshotElement for char {
+  fn to_snapshot(&self, _: &mut SnapshotWriter) -> u32 {
+    *self as u32
+  }
+
+  fn from_snapshot(
+    value: u32,
+    _: &mut SnapshotReader,
+  ) -> Result<Self, SnapshotError> {
+    char::from_u32(value).ok_or(SnapshotError::InvalidData)
+  }
+}
+
+/// Strings are stored as ids into the string table of the snapshot.
+impl SnapshotElement for String {
+  fn to_snapshot(&self, writer: &mut SnapshotWriter) -> u32 {
+    writer.intern(self)
+  }
+
+  fn from_snapshot(
+    value: u32,
+    reader: &mut SnapshotReader,
+  ) -> Result<Self, SnapshotError> {
+    reader.str_ref(value).map(str::to_string)
+  }
+}
+
+/// Anything that can be used to look up a [`Trie`] with elements of type `K`.
+pub trait TrieKey<K> {
+  type Elements<'a>: Iterator<Item = (usize, K)>
+  where
+    Self: 'a;
+
+  /// Yields the elements of the key, each together with the offset right
+  /// behind it. Offsets are byte offsets for strings and indices for slices.
+  fn elements(&self) -> Self::Elements<'_>;
+}
+
+impl TrieKey<char> for str {
+  type Elements<'a> = Map<CharIndices<'a>, fn((usize, char)) -> (usize, char)>;
+
+  fn elements(&self) -> Self::Elements<'_> {
+    self.char_indices().map(|(i, ch)| (i + ch.len_utf8(), ch))
+  }
+}
+
+impl<K: Clone> TrieKey<K> for [K] {
+  type Elements<'a>
+    = Map<Enumerate<Cloned<slice::Iter<'a, K>>>, fn((usize, K)) -> (usize, K)>
+  where
+    K: 'a;
+
+  fn elements(&self) -> Self::Elements<'_> {
+    self.iter().cloned().enumerate().map(|(i, x)| (i + 1, x))
+  }
+}
+
+impl<K: Clone> TrieKey<K> for Vec<K> {
+  type Elements<'a>
+    = <[K] as TrieKey<K>>::Elements<'a>
+  where
+    K: 'a;
+
+  fn elements(&self) -> Self::Elements<'_> {
+    self[..].elements()
+  }
+}
+
+/// A prefix tree keyed by sequences of `K`, by default characters. Children
+/// are kept sorted, so all iteration happens in lexicographic order, e.g. by
+/// code point for characters.
 #[derive(Debug, Clone)]
-pub struct Trie<T> {
-  data: HashMap<char, (Option<T>, Box<Trie<T>>)>,
+pub struct Trie<T, K = char> {
+  data: BTreeMap<K, Node<T, K>>,
 }
 
-impl<T> Default for Trie<T> {
+impl<T, K> Default for Trie<T, K> {
   fn default() -> Self {
-    Self::new()
+    Self {
+      data: BTreeMap::new(),
+    }
   }
 }
 
-impl<T> Trie<T> {
+impl<T, K: KeyElement> Trie<T, K> {
   pub fn new() -> Self {
-    Self {
-      data: HashMap::with_hasher(Default::default()),
-    }
+    Self::default()
   }
 
-  pub fn with_capacity(capacity: usize) -> Self {
-    Self {
-      data: HashMap::with_capacity_and_hasher(capacity, Default::default()),
+  fn node<I>(&self, mut elements: Peekable<I>) -> Option<&Node<T, K>>
+  where
+    I: Iterator<Item = K>,
+  {
+    let next_element = elements.next();
+
+    if let Some(element) = next_element {
+      let entry = self.data.get(&element);
+
+      if elements.peek().is_none() {
+        entry
+      } else {
+        entry.and_then(|entry| entry.1.node(elements))
+      }
+    } else {
+      None
     }
   }
 
-  fn entry<I>(
-    &self,
-    mut chars: Peekable<I>,
-  ) -> Option<&(Option<T>, Box<Trie<T>>)>
+  fn node_mut<I>(
+    &mut self,
+    mut elements: Peekable<I>,
+  ) -> Option<&mut Node<T, K>>
   where
-    I: Iterator<Item = char>,
+    I: Iterator<Item = K>,
   {
-    let next_char = chars.next();
+    let next_element = elements.next();
 
-    if let Some(ch) = next_char {
-      let entry = self.data.get(&ch);
+    if let Some(element) = next_element {
+      let entry = self.data.get_mut(&element);
 
-      if chars.peek().is_none() {
+      if elements.peek().is_none() {
         entry
       } else {
-        entry.and_then(|entry| entry.1.entry(chars))
+        entry.and_then(|entry| entry.1.node_mut(elements))
       }
     } else {
       None
     }
   }
 
-  fn entry_mut<I>(
+  fn node_mut_or_insert<I>(
     &mut self,
-    mut chars: Peekable<I>,
-  ) -> Option<&mut (Option<T>, Box<Trie<T>>)>
+    mut elements: Peekable<I>,
+  ) -> Option<&mut Node<T, K>>
   where
-    I: Iterator<Item = char>,
+    I: Iterator<Item = K>,
   {
-    let next_char = chars.next();
+    let next_element = elements.next();
 
-    if let Some(ch) = next_char {
-      let has_children = !chars.peek().is_none();
-      let entry = self.data.entry(ch).or_insert_with(|| {
-        (
-          None,
-          Box::new(Trie::with_capacity(if has_children { 1 } else { 0 })),
-        )
-      });
+    if let Some(element) = next_element {
+      let has_children = !elements.peek().is_none();
+      let entry = self
+        .data
+        .entry(element)
+        .or_insert_with(|| (None, Box::new(Trie::new())));
 
       if !has_children {
         Some(entry)
       } else {
-        entry.1.entry_mut(chars)
+        entry.1.node_mut_or_insert(elements)
       }
     } else {
       None
     }
   }
 
-  pub fn get(&self, key: &str) -> Option<&T> {
+  fn remove_node<I>(&mut self, mut elements: Peekable<I>) -> Option<T>
+  where
+    I: Iterator<Item = K>,
+  {
+    let element = elements.next()?;
+    let (value, children) = self.data.get_mut(&element)?;
+
+    let result = if elements.peek().is_none() {
+      value.take()
+    } else {
+      children.remove_node(elements)
+    };
+
+    if value.is_none() && children.is_empty() {
+      // Prune empty branch
+
+      self.data.remove(&element);
+    }
+
+    result
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.data.is_empty()
+  }
+
+  /// Returns the value and subtrie directly below `element`.
+  pub fn child(&self, element: &K) -> Option<(Option<&T>, &Trie<T, K>)> {
+    self
+      .data
+      .get(element)
+      .map(|(value, children)| (value.as_ref(), &**children))
+  }
+
+  /// Yields the elements directly below the root in lexicographic order,
+  /// together with their values and subtries.
+  pub fn children(
+    &self,
+  ) -> impl Iterator<Item = (&K, Option<&T>, &Trie<T, K>)> {
+    self.data.iter().map(|(element, (value, children))| {
+      (element, value.as_ref(), &**children)
+    })
+  }
+
+  pub fn get<Q: TrieKey<K> + ?Sized>(&self, key: &Q) -> Option<&T> {
     self
-      .entry(key.chars().peekable())
+      .node(key.elements().map(|(_, x)| x).peekable())
       .and_then(|(value, _)| value.as_ref())
   }
 
-  pub fn get_mut_or_insert(
+  pub fn get_mut<Q: TrieKey<K> + ?Sized>(&mut self, key: &Q) -> Option<&mut T> {
+    self
+      .node_mut(key.elements().map(|(_, x)| x).peekable())
+      .and_then(|(value, _)| value.as_mut())
+  }
+
+  pub fn get_mut_or_insert<Q: TrieKey<K> + ?Sized>(
     &mut self,
-    key: &str,
+    key: &Q,
     f: impl FnOnce() -> T,
   ) -> Option<&mut T> {
     self
-      .entry_mut(key.chars().peekable())
+      .node_mut_or_insert(key.elements().map(|(_, x)| x).peekable())
       .and_then(|(value, _)| {
         if value.is_none() {
           *value = Some(f());
@@ -97,23 +270,612 @@ impl<T> Trie<T> {
       })
   }
 
-  pub fn iter_prefix(&self, key: &str) -> Box<dyn Iterator<Item = &T> + '_> {
-    let mut chars = key.chars().peekable();
+  /// Stores `value` under `key` and returns the value it replaced. Empty keys
+  /// cannot be stored, in which case `value` is handed back as is.
+  pub fn insert<Q: TrieKey<K> + ?Sized>(
+    &mut self,
+    key: &Q,
+    value: T,
+  ) -> Result<Option<T>, T> {
+    match self.node_mut_or_insert(key.elements().map(|(_, x)| x).peekable()) {
+      Some((slot, _)) => Ok(slot.replace(value)),
+      None => Err(value),
+    }
+  }
+
+  /// Removes `key` and returns its value. Branches which are left without any
+  /// values are pruned.
+  pub fn remove<Q: TrieKey<K> + ?Sized>(&mut self, key: &Q) -> Option<T> {
+    self.remove_node(key.elements().map(|(_, x)| x).peekable())
+  }
+
+  pub fn entry<Q: TrieKey<K> + ?Sized>(&mut self, key: &Q) -> Entry<'_, T, K> {
+    Entry {
+      trie: self,
+      key: key.elements().map(|(_, x)| x).collect(),
+    }
+  }
+
+  /// Yields all keys with their values in lexicographic order.
+  pub fn iter(&self) -> Iter<'_, T, K> {
+    self.iter_prefix::<[K]>(&[])
+  }
+
+  /// Yields all keys starting with `key`, including `key` itself, with their
+  /// values in lexicographic order.
+  pub fn iter_prefix<Q: TrieKey<K> + ?Sized>(&self, key: &Q) -> Iter<'_, T, K> {
+    self.iter_prefix_with_depth(key, usize::MAX)
+  }
 
-    if chars.peek().is_none() {
-      // Return all entries
+  /// Like [`Trie::iter_prefix`], but only descends at most `max_depth`
+  /// elements beyond `key`.
+  pub fn iter_prefix_with_depth<Q: TrieKey<K> + ?Sized>(
+    &self,
+    key: &Q,
+    max_depth: usize,
+  ) -> Iter<'_, T, K> {
+    let key = key.elements().map(|(_, x)| x).collect::<Vec<_>>();
 
-      return Box::new(self.data.values().flat_map(|entry| {
-        entry.0.as_ref().into_iter().chain(entry.1.iter_prefix(""))
-      }));
+    if key.is_empty() {
+      return Iter {
+        key,
+        first: None,
+        stack: vec![self.data.iter()],
+        max_depth,
+      };
     }
 
-    let entry = self.entry(chars);
+    match self.node(key.iter().cloned().peekable()) {
+      Some((value, children)) => Iter {
+        key,
+        first: value.as_ref(),
+        stack: vec![children.data.iter()],
+        max_depth,
+      },
+      None => Iter {
+        key: vec![],
+        first: None,
+        stack: vec![],
+        max_depth,
+      },
+    }
+  }
 
-    if let Some(entry) = entry {
-      Box::new(entry.0.as_ref().into_iter().chain(entry.1.iter_prefix("")))
-    } else {
-      Box::new(None.into_iter())
+  /// Walks `text` once and yields every stored key that is a prefix of it,
+  /// shortest first, as the offset right behind the key together with its
+  /// value. For strings, this is the byte length of the key.
+  pub fn iter_common_prefixes<'a, 'b, Q: TrieKey<K> + ?Sized>(
+    &'a self,
+    text: &'b Q,
+  ) -> CommonPrefixes<'a, 'b, T, K, Q> {
+    CommonPrefixes {
+      trie: Some(self),
+      elements: text.elements(),
+    }
+  }
+
+  /// Returns all keys within Levenshtein distance `max_distance` of `key`,
+  /// ordered by distance first and then lexicographically.
+  pub fn find_within_distance<Q: TrieKey<K> + ?Sized>(
+    &self,
+    key: &Q,
+    max_distance: usize,
+  ) -> Vec<(K::Key, &T, usize)> {
+    fn inner<'a, T, K: KeyElement>(
+      trie: &'a Trie<T, K>,
+      target: &[K],
+      max_distance: usize,
+      prev_row: &[usize],
+      key: &mut Vec<K>,
+      result: &mut Vec<(K::Key, &'a T, usize)>,
+    ) {
+      for (element, value, children) in trie.children() {
+        let mut row = Vec::with_capacity(prev_row.len());
+
+        row.push(prev_row[0] + 1);
+
+        for (j, target_element) in target.iter().enumerate() {
+          let cost = if target_element == element { 0 } else { 1 };
+
+          row.push(
+            (row[j] + 1)
+              .min(prev_row[j + 1] + 1)
+              .min(prev_row[j] + cost),
+          );
+        }
+
+        key.push(element.clone());
+
+        let distance = row[target.len()];
+
+        if let Some(value) = value.filter(|_| distance <= max_distance) {
+          result.push((key.iter().cloned().collect(), value, distance));
+        }
+
+        // Distances can only grow further down, so prune as soon as every
+        // cell exceeds the bound
+
+        if row.iter().any(|&x| x <= max_distance) {
+          inner(children, target, max_distance, &row, key, result);
+        }
+
+        key.pop();
+      }
+    }
+
+    let target = key.elements().map(|(_, x)| x).collect::<Vec<_>>();
+    let first_row = (0..=target.len()).collect::<Vec<_>>();
+    let mut result = vec![];
+
+    inner(
+      self,
+      &target,
+      max_distance,
+      &first_row,
+      &mut vec![],
+      &mut result,
+    );
+
+    result.sort_by_key(|&(_, _, distance)| distance);
+    result
+  }
+}
+
+/// Number of u32 fields of a node in a snapshot: key element, index of the
+/// first child, number of children and whether there is a value.
+const SNAPSHOT_NODE_LEN: usize = 4;
+
+impl<T, K: KeyElement + SnapshotElement> Trie<T, K> {
+  /// Writes all nodes as a flat array in breadth-first order, so that the
+  /// children of each node are contiguous, followed by all values in the
+  /// same order.
+  pub fn write_snapshot(
+    &self,
+    writer: &mut SnapshotWriter,
+    write_value: &mut impl FnMut(&mut SnapshotWriter, &T),
+  ) {
+    let mut nodes = self.data.iter().collect::<Vec<_>>();
+    let mut first_children = Vec::with_capacity(nodes.len());
+    let mut i = 0;
+
+    while i < nodes.len() {
+      let (_, (_, children)) = nodes[i];
+
+      first_children.push(nodes.len());
+      nodes.extend(children.data.iter());
+      i += 1;
+    }
+
+    writer.write_len(self.data.len());
+    writer.write_len(nodes.len());
+
+    for (&(element, (value, children)), first_child) in
+      nodes.iter().zip(first_children)
+    {
+      let element = element.to_snapshot(writer);
+
+      writer.write_u32(element);
+      writer.write_len(first_child);
+      writer.write_len(children.data.len());
+      writer.write_u32(value.is_some() as u32);
     }
+
+    for (_, (value, _)) in nodes {
+      if let Some(value) = value {
+        write_value(writer, value);
+      }
+    }
+  }
+
+  pub fn read_snapshot(
+    reader: &mut SnapshotReader,
+    read_value: &mut impl FnMut(&mut SnapshotReader) -> Result<T, SnapshotError>,
+  ) -> Result<Self, SnapshotError> {
+    let root_len = reader.read_len()?;
+    let len = reader.read_len()?;
+    let nodes = reader.read_u32_array(len.saturating_mul(SNAPSHOT_NODE_LEN))?;
+    let mut values = (0..len)
+      .map(|i| match nodes.get(i * SNAPSHOT_NODE_LEN + 3) {
+        0 => Ok(None),
+        1 => read_value(reader).map(Some),
+        _ => Err(SnapshotError::InvalidData),
+      })
+      .collect::<Result<Vec<_>, _>>()?;
+
+    let mut elements = Vec::with_capacity(len);
+    let mut child_ranges = Vec::with_capacity(len);
+    let mut next_child = root_len;
+
+    for i in 0..len {
+      let node = i * SNAPSHOT_NODE_LEN;
+      let first_child = nodes.get(node + 1) as usize;
+      let child_len = nodes.get(node + 2) as usize;
+
+      // Every node but the roots has to be a child of an earlier node, and
+      // children are stored right after the children of the previous node,
+      // which rules out cycles as well as shared or overlapping children
+
+      if i >= next_child || first_child != next_child {
+        return Err(SnapshotError::InvalidData);
+      }
+
+      next_child = next_child.saturating_add(child_len);
+      elements.push(K::from_snapshot(nodes.get(node), reader)?);
+      child_ranges.push(first_child..next_child);
+    }
+
+    if next_child != len {
+      return Err(SnapshotError::InvalidData);
+    }
+
+    // Children always come after their parent, so building the nodes back
+    // to front never needs any recursion
+
+    let mut built = Vec::with_capacity(len);
+
+    built.resize_with(len, || None);
+
+    for (i, element) in elements.into_iter().enumerate().rev() {
+      let children = Self {
+        data: built[child_ranges[i].clone()]
+          .iter_mut()
+          .filter_map(Option::take)
+          .collect(),
+      };
+
+      built[i] = Some((element, (values[i].take(), Box::new(children))));
+    }
+
+    Ok(Self {
+      data: built[..root_len]
+        .iter_mut()
+        .filter_map(Option::take)
+        .collect(),
+    })
+  }
+}
+
+/// A view into a single key of a [`Trie`], which may or may not hold a value.
+/// No nodes are created until a value is actually inserted.
+pub struct Entry<'a, T, K = char> {
+  trie: &'a mut Trie<T, K>,
+  key: Vec<K>,
+}
+
+impl<'a, T, K: KeyElement> Entry<'a, T, K> {
+  pub fn key(&self) -> &[K] {
+    &self.key
+  }
+
+  pub fn get(&self) -> Option<&T> {
+    self.trie.get(&self.key)
+  }
+
+  pub fn get_mut(&mut self) -> Option<&mut T> {
+    self.trie.get_mut(&self.key)
+  }
+
+  pub fn and_modify(mut self, f: impl FnOnce(&mut T)) -> Self {
+    if let Some(value) = self.get_mut() {
+      f(value);
+    }
+
+    self
+  }
+
+  pub fn or_insert_with(self, f: impl FnOnce() -> T) -> Option<&'a mut T> {
+    self.trie.get_mut_or_insert(&self.key, f)
+  }
+
+  pub fn or_insert(self, value: T) -> Option<&'a mut T> {
+    self.or_insert_with(|| value)
+  }
+
+  pub fn or_default(self) -> Option<&'a mut T>
+  where
+    T: Default,
+  {
+    self.or_insert_with(T::default)
+  }
+
+  pub fn insert(self, value: T) -> Result<Option<T>, T> {
+    self.trie.insert(&self.key, value)
+  }
+
+  pub fn remove(self) -> Option<T> {
+    self.trie.remove(&self.key)
+  }
+}
+
+pub struct Iter<'a, T, K = char> {
+  key: Vec<K>,
+  first: Option<&'a T>,
+  stack: Vec<btree_map::Iter<'a, K, Node<T, K>>>,
+  max_depth: usize,
+}
+
+impl<'a, T, K: KeyElement> Iterator for Iter<'a, T, K> {
+  type Item = (K::Key, &'a T);
+
+  fn next(&mut self) -> Option<Self::Item> {
+    if let Some(value) = self.first.take() {
+      return Some((self.key.iter().cloned().collect(), value));
+    }
+
+    loop {
+      let depth = self.stack.len();
+      let next = self.stack.last_mut()?.next();
+
+      match next {
+        Some((element, (value, children))) if depth <= self.max_depth => {
+          self.key.push(element.clone());
+          self.stack.push(children.data.iter());
+
+          if let Some(value) = value {
+            return Some((self.key.iter().cloned().collect(), value));
+          }
+        }
+        _ => {
+          // Children exhausted or too deep, go back up
+
+          self.stack.pop();
+          self.key.pop();
+        }
+      }
+    }
+  }
+}
+
+pub struct CommonPrefixes<'a, 'b, T, K, Q: TrieKey<K> + ?Sized + 'b> {
+  trie: Option<&'a Trie<T, K>>,
+  elements: Q::Elements<'b>,
+}
+
+impl<'a, 'b, T, K: KeyElement, Q: TrieKey<K> + ?Sized> Iterator
+  for CommonPrefixes<'a, 'b, T, K, Q>
+{
+  type Item = (usize, &'a T);
+
+  fn next(&mut self) -> Option<Self::Item> {
+    while let Some(trie) = self.trie.take() {
+      let (offset, element) = self.elements.next()?;
+      let (value, children) = trie.data.get(&element)?;
+
+      self.trie = Some(children);
+
+      if let Some(value) = value {
+        return Some((offset, value));
+      }
+    }
+
+    None
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::{Trie, SNAPSHOT_NODE_LEN};
+  use crate::{SnapshotError, SnapshotKind, SnapshotReader, SnapshotWriter};
+
+  #[test]
+  fn should_find_all_common_prefixes() {
+    let mut trie = Trie::new();
+
+    for word in ["中", "中国", "中国人", "国人", "中文"] {
+      trie.get_mut_or_insert(word, || word);
+    }
+
+    assert_eq!(
+      trie.iter_common_prefixes("中国人民").collect::<Vec<_>>(),
+      vec![(3, &"中"), (6, &"中国"), (9, &"中国人")]
+    );
+    assert_eq!(trie.iter_common_prefixes("人民").count(), 0);
+    assert_eq!(trie.iter_common_prefixes("").count(), 0);
+  }
+
+  #[test]
+  fn should_insert_and_remove_keys() {
+    let mut trie = Trie::new();
+
+    assert_eq!(trie.insert("中国", 1), Ok(None));
+    assert_eq!(trie.insert("中国人", 2), Ok(None));
+    assert_eq!(trie.insert("中国", 3), Ok(Some(1)));
+    assert_eq!(trie.insert("", 4), Err(4));
+
+    *trie.get_mut("中国人").unwrap() += 10;
+
+    assert_eq!(trie.get("中国人"), Some(&12));
+    assert_eq!(trie.get_mut("中"), None);
+    assert_eq!(trie.remove("中"), None);
+    assert_eq!(trie.remove("中国"), Some(3));
+    assert_eq!(trie.get("中国"), None);
+    assert_eq!(trie.get("中国人"), Some(&12));
+    assert_eq!(trie.remove("中国人"), Some(12));
+    assert!(trie.is_empty());
+  }
+
+  #[test]
+  fn should_modify_values_through_entries() {
+    let mut trie = Trie::<Vec<i32>>::new();
+
+    trie.entry("中国").or_default().unwrap().push(1);
+    trie
+      .entry("中国")
+      .and_modify(|vec| vec.push(2))
+      .or_default();
+    trie.entry("中国人").and_modify(|vec| vec.push(3));
+
+    assert_eq!(trie.get("中国"), Some(&vec![1, 2]));
+    assert_eq!(trie.get("中国人"), None);
+    assert_eq!(trie.iter().count(), 1);
+    assert_eq!(trie.entry("中国").remove(), Some(vec![1, 2]));
+    assert!(trie.is_empty());
+  }
+
+  #[test]
+  fn should_round_trip_snapshot() {
+    let mut trie = Trie::<u32, String>::new();
+
+    for (i, key) in [["zhong1", "guo2"], ["zhong1", "wen2"], ["mei3", "guo2"]]
+      .into_iter()
+      .enumerate()
+    {
+      trie.insert(&key.map(str::to_string)[..], i as u32).unwrap();
+    }
+
+    trie.insert(&["zhong1".to_string()][..], 3).unwrap();
+
+    let mut writer = SnapshotWriter::new();
+
+    trie.write_snapshot(&mut writer, &mut |writer, value| {
+      writer.write_u32(*value)
+    });
+
+    let data = writer.finish(SnapshotKind::Word);
+    let mut reader = SnapshotReader::new(&data, SnapshotKind::Word).unwrap();
+    let read = Trie::<u32, String>::read_snapshot(&mut reader, &mut |reader| {
+      reader.read_u32()
+    })
+    .unwrap();
+
+    assert_eq!(reader.finish(), Ok(()));
+    assert!(read.iter().eq(trie.iter()));
+  }
+
+  #[test]
+  fn should_reject_snapshots_with_shared_children() {
+    let read = |nodes: &[[u32; SNAPSHOT_NODE_LEN]]| {
+      let mut writer = SnapshotWriter::new();
+
+      writer.write_len(2);
+      writer.write_len(nodes.len());
+
+      for &node in nodes {
+        for field in node {
+          writer.write_u32(field);
+        }
+      }
+
+      for _ in nodes.iter().filter(|node| node[3] == 1) {
+        writer.write_u32(0);
+      }
+
+      let data = writer.finish(SnapshotKind::Word);
+      let mut reader = SnapshotReader::new(&data, SnapshotKind::Word).unwrap();
+
+      Trie::<u32>::read_snapshot(&mut reader, &mut |reader| reader.read_u32())
+        .map(|trie| trie.iter().map(|(key, _)| key).collect::<Vec<_>>())
+    };
+
+    let a = 'a' as u32;
+    let b = 'b' as u32;
+    let c = 'c' as u32;
+
+    assert_eq!(
+      read(&[[a, 2, 1, 0], [b, 3, 0, 0], [c, 3, 0, 1]]),
+      Ok(vec!["ac".to_string()])
+    );
+    assert_eq!(
+      read(&[[a, 2, 1, 0], [b, 2, 1, 0], [c, 3, 0, 1]]),
+      Err(SnapshotError::InvalidData)
+    );
+    assert_eq!(
+      read(&[[a, 2, 1, 0], [b, 3, 0, 0], [c, 2, 1, 1]]),
+      Err(SnapshotError::InvalidData)
+    );
+    assert_eq!(
+      read(&[[a, 2, 0, 0], [b, 2, 0, 0], [c, 3, 0, 1]]),
+      Err(SnapshotError::InvalidData)
+    );
+  }
+
+  #[test]
+  fn should_find_keys_within_distance() {
+    let mut trie = Trie::new();
+
+    for word in ["中国", "中国人", "中文", "美国", "国人", "外国人民"]
+    {
+      trie.insert(word, ()).unwrap();
+    }
+
+    let find = |key, max_distance| {
+      trie
+        .find_within_distance(key, max_distance)
+        .into_iter()
+        .map(|(key, _, distance)| (key, distance))
+        .collect::<Vec<_>>()
+    };
+
+    assert_eq!(
+      find("中国", 1),
+      vec![
+        ("中国".to_string(), 0),
+        ("中国人".to_string(), 1),
+        ("中文".to_string(), 1),
+        ("美国".to_string(), 1),
+      ]
+    );
+    assert_eq!(find("外国", 0), vec![]);
+    assert_eq!(find("外国人", 1).len(), 3);
+  }
+
+  #[test]
+  fn should_support_other_key_types() {
+    let mut trie = Trie::<&str, String>::new();
+    let key = |syllables: &str| {
+      syllables
+        .split(' ')
+        .map(|syllable| syllable.to_string())
+        .collect::<Vec<_>>()
+    };
+
+    trie.insert(&key("zhong1 guo2"), "中国").unwrap();
+    trie.insert(&key("zhong1 guo2 ren2"), "中国人").unwrap();
+    trie.insert(&key("zhong1 wen2"), "中文").unwrap();
+
+    assert_eq!(trie.get(&key("zhong1 guo2")), Some(&"中国"));
+    assert_eq!(trie.get(&key("zhong1")), None);
+    assert_eq!(
+      trie.iter_prefix(&key("zhong1 guo2")).collect::<Vec<_>>(),
+      vec![
+        (key("zhong1 guo2"), &"中国"),
+        (key("zhong1 guo2 ren2"), &"中国人")
+      ]
+    );
+    assert_eq!(
+      trie
+        .iter_common_prefixes(&key("zhong1 guo2 ren2 min2"))
+        .collect::<Vec<_>>(),
+      vec![(2, &"中国"), (3, &"中国人")]
+    );
+  }
+
+  #[test]
+  fn should_iterate_in_lexicographic_order() {
+    let mut trie = Trie::new();
+
+    for word in ["中国人", "国人", "中", "中文", "中国"] {
+      trie.get_mut_or_insert(word, || ());
+    }
+
+    assert_eq!(
+      trie.iter().map(|(key, _)| key).collect::<Vec<_>>(),
+      vec!["中", "中国", "中国人", "中文", "国人"]
+    );
+    assert_eq!(
+      trie
+        .iter_prefix("中国")
+        .map(|(key, _)| key)
+        .collect::<Vec<_>>(),
+      vec!["中国", "中国人"]
+    );
+    assert_eq!(
+      trie
+        .iter_prefix_with_depth("中", 1)
+        .map(|(key, _)| key)
+        .collect::<Vec<_>>(),
+      vec!["中", "中国", "中文"]
+    );
+    assert_eq!(trie.iter_prefix("人").count(), 0);
   }
 }
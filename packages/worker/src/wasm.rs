@@ -1,31 +1,130 @@
-use std::{cell::RefCell, future::Future, pin::Pin, rc::Rc};
+use std::{
+  cell::RefCell, cmp::Reverse, future::Future, pin::Pin, rc::Rc, sync::Arc,
+};
 
-use js_sys::{Array, Promise};
+use js_sys::{Array, ArrayBuffer, Promise, Uint8Array};
 use once_cell::unsync::OnceCell;
-use wasm_bindgen::{prelude::wasm_bindgen, JsValue, UnwrapThrowExt};
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::{
+  prelude::wasm_bindgen, throw_str, JsCast, JsValue, UnwrapThrowExt,
+};
 use wasm_bindgen_futures::JsFuture;
 
 use crate::{
   character::{CharacterDecomposition, CharacterDictionary, CharacterEntry},
-  word::{Token, WordDictionary, WordEntry},
-  DictionaryType, FrequencyDictionary, SentenceDictionary, ThesaurusDictionary,
+  word::{
+    Conversion, HomophoneMode, Segmentation, Span, SpanUnit, Token, TokenKind,
+    TokenizeMode, WordDictionary, WordEntry,
+  },
+  DictionaryMode, DictionarySource, DictionaryType, EnglishIndex,
+  FrequencyDictionary, OverlayMode, ParseErrorKind, ParseReport, PinyinFormat,
+  PinyinQuery, ScriptDetection, SentenceDictionary, SnapshotError,
+  SnapshotKind, ThesaurusDictionary, WordMatch, WordPattern, WordScanner,
 };
 
 #[wasm_bindgen(typescript_custom_section)]
 const TYPESCRIPT_TYPES: &'static str = r#"
+  export type TokenKind =
+    | "chineseWord"
+    | "unknownHanzi"
+    | "punctuation"
+    | "whitespace"
+    | "latin"
+    | "number"
+    | "symbol";
+
   export interface Token {
     value: string;
-    offset: number;
+    start: number;
+    end: number;
     hasEntries: boolean;
+    kind: TokenKind;
+  }
+
+  export interface Segmentation {
+    tokens: Token[];
+    score: number;
+  }
+
+  export interface Ambiguity {
+    source: Token;
+    target: Token;
+    candidates: string[];
+  }
+
+  export interface Conversion {
+    text: string;
+    ambiguities: Ambiguity[];
+  }
+
+  export interface ScriptDetection {
+    script: "simplified" | "traditional" | "mixed" | "undetermined";
+    confidence: number;
+    simplified: number;
+    traditional: number;
+  }
+
+  export interface ParseError {
+    line: number;
+    kind:
+      | { type: "missingField"; value: string }
+      | {
+        type:
+          | "invalidPinyin"
+          | "invalidJyutping"
+          | "invalidDefinition"
+          | "emptyDefinition";
+      }
+      | { type: "invalidNumber" | "invalidJson"; value: string };
+    message: string;
+  }
+
+  export interface ParseReport {
+    entries: number;
+    skipped: number;
+    errors: ParseError[];
+  }
+
+  export interface WordMatch {
+    value: string;
+    start: number;
+    end: number;
   }
 
   export interface WordEntry {
     traditional: string;
     simplified: string;
     pinyin: string;
+    jyutping?: string;
     english: string;
+    source: string;
+  }
+
+  export interface UserDictionary {
+    name: string;
+    data: string;
+    priority?: number;
+    mode?: "shadow" | "append";
+    readingsOnly?: boolean;
+  }
+
+  export interface WordLink {
+    traditional: string;
+    simplified: string;
+    pinyin?: string;
+    entry?: WordEntry;
   }
 
+  export type Sense =
+    | { type: "gloss"; text: string }
+    | { type: "classifiers"; classifiers: WordLink[] }
+    | {
+      type: "variantOf" | "oldVariantOf" | "seeAlso" | "abbreviationOf";
+      text: string;
+      links: WordLink[];
+    }
+    | { type: "surname" | "taxon"; text: string; name: string };
+
   export interface CharacterEntry {
     character: string;
     definition?: string;
@@ -62,14 +161,49 @@ extern "C" {
   #[wasm_bindgen(typescript_type = "Token[]")]
   pub type JsTokenArray;
 
+  #[wasm_bindgen(typescript_type = "Token[][]")]
+  pub type JsLattice;
+
+  #[wasm_bindgen(typescript_type = "Segmentation")]
+  pub type JsSegmentation;
+
+  #[wasm_bindgen(typescript_type = "Segmentation[]")]
+  pub type JsSegmentationArray;
+
+  #[wasm_bindgen(typescript_type = "Conversion")]
+  pub type JsConversion;
+
+  #[wasm_bindgen(typescript_type = "ScriptDetection")]
+  pub type JsScriptDetection;
+
+  #[wasm_bindgen(typescript_type = "ParseReport | undefined")]
+  pub type JsParseReport;
+
+  #[wasm_bindgen(typescript_type = "WordMatch")]
+  pub type JsWordMatch;
+
+  #[wasm_bindgen(typescript_type = "WordMatch[]")]
+  pub type JsWordMatchArray;
+
   #[wasm_bindgen(typescript_type = "WordEntry")]
   pub type JsWordEntry;
 
   #[wasm_bindgen(typescript_type = "WordEntry[]")]
   pub type JsWordEntryArray;
 
-  #[wasm_bindgen(typescript_type = "[entry: WordEntry, exact: boolean][]")]
-  pub type JsWordEntryExactArray;
+  #[wasm_bindgen(typescript_type = "[entry: WordEntry, distance: number][]")]
+  pub type JsWordEntryDistanceArray;
+
+  #[wasm_bindgen(
+    typescript_type = "[entry: WordEntry, mode: \"exact\" | \"toneInsensitive\" | \"confusable\"][]"
+  )]
+  pub type JsWordEntryHomophoneModeArray;
+
+  #[wasm_bindgen(typescript_type = "[entry: WordEntry, senses: Sense[]][]")]
+  pub type JsWordEntrySensesArray;
+
+  #[wasm_bindgen(typescript_type = "WordLink[]")]
+  pub type JsWordLinkArray;
 
   #[wasm_bindgen(typescript_type = "CharacterEntry")]
   pub type JsCharacterEntry;
@@ -93,9 +227,160 @@ extern "C" {
   pub type JsWordScoreArray;
 }
 
-impl<'a> From<&'a Token> for JsToken {
-  fn from(value: &'a Token) -> Self {
-    serde_wasm_bindgen::to_value(value).unwrap_throw().into()
+/// Serialized form of a token with its span in a single unit.
+#[derive(Serialize)]
+struct TokenData<'a> {
+  value: &'a str,
+  start: usize,
+  end: usize,
+  #[serde(rename = "hasEntries")]
+  has_entries: bool,
+  kind: TokenKind,
+}
+
+impl<'a> TokenData<'a> {
+  fn new(token: &'a Token, unit: SpanUnit) -> Self {
+    let Span { start, end } = token.span(unit);
+
+    Self {
+      value: &token.value,
+      start,
+      end,
+      has_entries: token.has_entries,
+      kind: token.kind,
+    }
+  }
+}
+
+/// Serialized form of a word match with its span in a single unit.
+#[derive(Serialize)]
+struct WordMatchData<'a> {
+  value: &'a str,
+  start: usize,
+  end: usize,
+}
+
+#[derive(Serialize)]
+struct SegmentationData<'a> {
+  tokens: Vec<TokenData<'a>>,
+  score: f32,
+}
+
+#[derive(Serialize)]
+struct AmbiguityData<'a> {
+  source: TokenData<'a>,
+  target: TokenData<'a>,
+  candidates: &'a [Arc<str>],
+}
+
+#[derive(Serialize)]
+struct ConversionData<'a> {
+  text: &'a str,
+  ambiguities: Vec<AmbiguityData<'a>>,
+}
+
+/// A user dictionary in CEDICT format given to the `Worker`. Unless given,
+/// the priority is 1, i.e. above CEDICT. Dictionaries only listing readings
+/// add their Jyutping to existing entries instead.
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct UserDictionaryData {
+  name: String,
+  data: String,
+  priority: Option<i32>,
+  #[serde(default)]
+  mode: OverlayMode,
+  #[serde(default)]
+  readings_only: bool,
+}
+
+#[derive(Serialize)]
+struct ParseErrorData<'a> {
+  line: usize,
+  kind: &'a ParseErrorKind,
+  message: String,
+}
+
+#[derive(Serialize)]
+struct ParseReportData<'a> {
+  entries: usize,
+  skipped: usize,
+  errors: Vec<ParseErrorData<'a>>,
+}
+
+impl JsToken {
+  fn new(token: &Token, unit: SpanUnit) -> Self {
+    serde_wasm_bindgen::to_value(&TokenData::new(token, unit))
+      .unwrap_throw()
+      .into()
+  }
+}
+
+impl JsSegmentation {
+  fn new(segmentation: &Segmentation, unit: SpanUnit) -> Self {
+    let data = SegmentationData {
+      tokens: segmentation
+        .tokens
+        .iter()
+        .map(|token| TokenData::new(token, unit))
+        .collect(),
+      score: segmentation.score,
+    };
+
+    serde_wasm_bindgen::to_value(&data).unwrap_throw().into()
+  }
+}
+
+impl JsConversion {
+  fn new(conversion: &Conversion, unit: SpanUnit) -> Self {
+    let data = ConversionData {
+      text: &conversion.text,
+      ambiguities: conversion
+        .ambiguities
+        .iter()
+        .map(|ambiguity| AmbiguityData {
+          source: TokenData::new(&ambiguity.source, unit),
+          target: TokenData::new(&ambiguity.target, unit),
+          candidates: &ambiguity.candidates,
+        })
+        .collect(),
+    };
+
+    serde_wasm_bindgen::to_value(&data).unwrap_throw().into()
+  }
+}
+
+impl<'a> From<&'a ParseReport> for JsParseReport {
+  fn from(value: &'a ParseReport) -> Self {
+    let data = ParseReportData {
+      entries: value.entries,
+      skipped: value.skipped,
+      errors: value
+        .errors
+        .iter()
+        .map(|err| ParseErrorData {
+          line: err.line,
+          kind: &err.kind,
+          message: err.kind.to_string(),
+        })
+        .collect(),
+    };
+
+    serde_wasm_bindgen::to_value(&data).unwrap_throw().into()
+  }
+}
+
+impl JsWordMatch {
+  fn new(word: &WordMatch, unit: SpanUnit) -> Self {
+    let Span { start, end } = word.span(unit);
+
+    serde_wasm_bindgen::to_value(&WordMatchData {
+      value: word.value,
+      start,
+      end,
+    })
+    .unwrap_throw()
+    .into()
   }
 }
 
@@ -117,6 +402,92 @@ impl<'a> From<&'a CharacterDecomposition> for JsCharacterDecomposition {
   }
 }
 
+/// Dictionary data is either given as text, which needs to be parsed, or as
+/// a binary snapshot in an `ArrayBuffer` or `Uint8Array`.
+pub enum DictionaryData {
+  Text(String),
+  Snapshot(Vec<u8>),
+}
+
+impl From<Result<JsValue, JsValue>> for DictionaryData {
+  fn from(value: Result<JsValue, JsValue>) -> Self {
+    // Snapshots are copied into wasm memory once and read in place from there
+
+    match value {
+      Ok(value) if value.is_instance_of::<Uint8Array>() => {
+        DictionaryData::Snapshot(value.unchecked_into::<Uint8Array>().to_vec())
+      }
+      Ok(value) if value.is_instance_of::<ArrayBuffer>() => {
+        DictionaryData::Snapshot(Uint8Array::new(&value).to_vec())
+      }
+      value => DictionaryData::Text(
+        value
+          .ok()
+          .and_then(|data| data.as_string())
+          .unwrap_or_default(),
+      ),
+    }
+  }
+}
+
+/// Report of a dictionary by kind and, for user dictionaries, by name
+type ReportEntry = (SnapshotKind, Option<String>, ParseReport);
+
+/// Keeps the report of a dictionary parsed from text for the `Worker` to
+/// show.
+fn add_report<T>(
+  reports: &RefCell<Vec<ReportEntry>>,
+  kind: SnapshotKind,
+  (dict, report): (T, ParseReport),
+) -> T {
+  reports.borrow_mut().push((kind, None, report));
+  dict
+}
+
+fn unwrap_snapshot<T>(result: Result<T, SnapshotError>) -> T {
+  result.unwrap_or_else(|err| throw_str(&err.to_string()))
+}
+
+/// Overlays a user dictionary on the word dictionary. Sources which are
+/// already part of it, as it has been loaded from a snapshot taken after
+/// overlaying them, are skipped instead of being added a second time.
+/// Readings are merged regardless, as merging them again changes nothing.
+fn overlay_user_dict(
+  word_dict: &mut WordDictionary,
+  user_dict: &UserDictionaryData,
+) -> Option<ParseReport> {
+  if user_dict.readings_only {
+    Some(word_dict.merge_jyutping(&user_dict.data))
+  } else if word_dict.source(&user_dict.name).is_some() {
+    None
+  } else {
+    Some(word_dict.add_source(
+      &user_dict.data,
+      DictionarySource::new(
+        &user_dict.name,
+        user_dict.priority.unwrap_or(1),
+        user_dict.mode,
+      ),
+    ))
+  }
+}
+
+/// Loads the thesaurus from a snapshot if one is given, and builds it from
+/// the word dictionary otherwise.
+fn load_thesaurus(
+  data: Option<DictionaryData>,
+  word_dict: &WordDictionary,
+) -> Result<ThesaurusDictionary, SnapshotError> {
+  match data {
+    Some(DictionaryData::Snapshot(data)) => {
+      ThesaurusDictionary::from_snapshot(&data)
+    }
+    Some(DictionaryData::Text(_)) | None => {
+      Ok(ThesaurusDictionary::new(word_dict))
+    }
+  }
+}
+
 pub struct MaybeDone<T> {
   promise: RefCell<Option<Promise>>,
   f: Rc<dyn Fn(Result<JsValue, JsValue>) -> Pin<Box<dyn Future<Output = T>>>>,
@@ -169,10 +540,26 @@ impl<T> MaybeDone<T> {
 #[wasm_bindgen]
 pub struct Worker {
   word_dict: MaybeDone<WordDictionary>,
+  word_scanner: MaybeDone<WordScanner>,
+  english_index: MaybeDone<EnglishIndex>,
   character_dict: MaybeDone<CharacterDictionary>,
   frequency_dict: MaybeDone<FrequencyDictionary>,
   sentences_dict: MaybeDone<SentenceDictionary>,
   thesaurus_dict: MaybeDone<ThesaurusDictionary>,
+  /// Reports of all dictionaries parsed from text so far
+  reports: Rc<RefCell<Vec<ReportEntry>>>,
+}
+
+impl Worker {
+  async fn dictionary_type(
+    &self,
+    input: &str,
+    mode: Option<DictionaryMode>,
+  ) -> DictionaryType {
+    mode
+      .unwrap_or(DictionaryMode::Auto)
+      .resolve(input, self.word_dict.get().await)
+  }
 }
 
 #[wasm_bindgen]
@@ -183,133 +570,589 @@ impl Worker {
     character_dict_data: Promise,
     frequency_dict_data: Promise,
     sentences_dict_data: Promise,
+    thesaurus_dict_data: Option<Promise>,
+    user_dict_data: Option<Promise>,
   ) -> Self {
-    let word_dict = MaybeDone::new(&word_dict_data, |data| {
-      Box::pin(async {
-        let data = data
-          .ok()
-          .and_then(|data| data.as_string())
-          .unwrap_or_default();
+    let reports = Rc::new(RefCell::new(vec![]));
 
-        WordDictionary::new(&data)
-      })
-    });
+    let word_dict = MaybeDone::new(&word_dict_data, {
+      let reports = reports.clone();
 
-    let character_dict = MaybeDone::new(&character_dict_data, |data| {
-      Box::pin(async {
-        let data = data
-          .ok()
-          .and_then(|data| data.as_string())
-          .unwrap_or_default();
+      move |data| {
+        let reports = reports.clone();
+        let user_dict_data = user_dict_data.clone();
 
-        CharacterDictionary::new(&data)
-      })
+        Box::pin(async move {
+          let mut word_dict = match DictionaryData::from(data) {
+            DictionaryData::Text(data) => add_report(
+              &reports,
+              SnapshotKind::Word,
+              WordDictionary::parse_with_report(&data),
+            ),
+            DictionaryData::Snapshot(data) => {
+              unwrap_snapshot(WordDictionary::from_snapshot(&data))
+            }
+          };
+
+          // User dictionaries are overlaid in the given order, so that
+          // everything derived from words already knows about them
+
+          let user_dicts: Option<Vec<UserDictionaryData>> = match user_dict_data
+          {
+            Some(promise) => match JsFuture::from(promise).await {
+              Ok(value) => serde_wasm_bindgen::from_value(value)
+                .unwrap_or_else(|err| throw_str(&err.to_string())),
+              Err(err) => {
+                throw_str(&format!("could not load user dictionaries: {err:?}"))
+              }
+            },
+            None => None,
+          };
+
+          for user_dict in user_dicts.into_iter().flatten() {
+            if let Some(report) = overlay_user_dict(&mut word_dict, &user_dict)
+            {
+              reports.borrow_mut().push((
+                SnapshotKind::Word,
+                Some(user_dict.name),
+                report,
+              ));
+            }
+          }
+
+          word_dict
+        })
+      }
     });
 
-    let frequency_dict = MaybeDone::new(&frequency_dict_data, |data| {
-      Box::pin(async {
-        let data = data
-          .ok()
-          .and_then(|data| data.as_string())
-          .unwrap_or_default();
+    let word_scanner = MaybeDone::new(&word_dict_data, {
+      let word_dict = word_dict.clone();
 
-        FrequencyDictionary::new(&data)
-      })
+      move |_| {
+        let word_dict = word_dict.clone();
+
+        Box::pin(async move { WordScanner::new(word_dict.get().await) })
+      }
     });
 
-    let sentences_dict = MaybeDone::new(&sentences_dict_data, {
+    let english_index = MaybeDone::new(&word_dict_data, {
       let word_dict = word_dict.clone();
 
-      move |data| {
+      move |_| {
         let word_dict = word_dict.clone();
 
+        Box::pin(async move { EnglishIndex::new(word_dict.get().await) })
+      }
+    });
+
+    let character_dict = MaybeDone::new(&character_dict_data, {
+      let reports = reports.clone();
+
+      move |data| {
+        let reports = reports.clone();
+
         Box::pin(async move {
-          let data = data
-            .ok()
-            .and_then(|data| data.as_string())
-            .unwrap_or_default();
+          match DictionaryData::from(data) {
+            DictionaryData::Text(data) => add_report(
+              &reports,
+              SnapshotKind::Character,
+              CharacterDictionary::parse_with_report(&data),
+            ),
+            DictionaryData::Snapshot(data) => {
+              unwrap_snapshot(CharacterDictionary::from_snapshot(&data))
+            }
+          }
+        })
+      }
+    });
 
-          SentenceDictionary::new(&data, word_dict.get().await)
+    let frequency_dict = MaybeDone::new(&frequency_dict_data, {
+      let reports = reports.clone();
+
+      move |data| {
+        let reports = reports.clone();
+
+        Box::pin(async move {
+          match DictionaryData::from(data) {
+            DictionaryData::Text(data) => add_report(
+              &reports,
+              SnapshotKind::Frequency,
+              FrequencyDictionary::parse_with_report(&data),
+            ),
+            DictionaryData::Snapshot(data) => {
+              unwrap_snapshot(FrequencyDictionary::from_snapshot(&data))
+            }
+          }
         })
       }
     });
 
-    let thesaurus_dict = MaybeDone::new(&sentences_dict_data, {
+    let sentences_dict = MaybeDone::new(&sentences_dict_data, {
       let word_dict = word_dict.clone();
+      let reports = reports.clone();
 
-      move |_| {
+      move |data| {
         let word_dict = word_dict.clone();
+        let reports = reports.clone();
 
-        Box::pin(async move { ThesaurusDictionary::new(word_dict.get().await) })
+        Box::pin(async move {
+          match DictionaryData::from(data) {
+            DictionaryData::Text(data) => add_report(
+              &reports,
+              SnapshotKind::Sentence,
+              SentenceDictionary::parse_with_report(
+                &data,
+                word_dict.get().await,
+              ),
+            ),
+            DictionaryData::Snapshot(data) => {
+              unwrap_snapshot(SentenceDictionary::from_snapshot(&data))
+            }
+          }
+        })
       }
     });
 
+    // Unless thesaurus data is given, the thesaurus is built after sentences
+    // have been loaded to not block more important work. The sentence data
+    // itself is never read here, as it may be a snapshot of another kind
+
+    let thesaurus_dict = MaybeDone::new(
+      thesaurus_dict_data.as_ref().unwrap_or(&sentences_dict_data),
+      {
+        let word_dict = word_dict.clone();
+        let has_thesaurus_data = thesaurus_dict_data.is_some();
+
+        move |data| {
+          let word_dict = word_dict.clone();
+          let data = has_thesaurus_data.then(|| DictionaryData::from(data));
+
+          Box::pin(async move {
+            unwrap_snapshot(load_thesaurus(data, word_dict.get().await))
+          })
+        }
+      },
+    );
+
     Self {
       word_dict,
+      word_scanner,
+      english_index,
       character_dict,
       frequency_dict,
       sentences_dict,
       thesaurus_dict,
+      reports,
+    }
+  }
+
+  #[wasm_bindgen(js_name = "getParseReport")]
+  pub async fn get_parse_report(
+    &self,
+    kind: SnapshotKind,
+    user_dict: Option<String>,
+  ) -> JsParseReport {
+    // Wait for the dictionary to be loaded. Dictionaries loaded from snapshots
+    // and the thesaurus, which is derived from words, have no report.
+
+    match kind {
+      SnapshotKind::Word => {
+        self.word_dict.get().await;
+      }
+      SnapshotKind::Character => {
+        self.character_dict.get().await;
+      }
+      SnapshotKind::Frequency => {
+        self.frequency_dict.get().await;
+      }
+      SnapshotKind::Sentence => {
+        self.sentences_dict.get().await;
+      }
+      SnapshotKind::Thesaurus => {
+        self.thesaurus_dict.get().await;
+      }
     }
+
+    self
+      .reports
+      .borrow()
+      .iter()
+      .find(|(other, name, _)| *other == kind && *name == user_dict)
+      .map(|(_, _, report)| JsParseReport::from(report))
+      .unwrap_or_else(|| JsValue::UNDEFINED.into())
   }
 
-  pub async fn tokenize(&self, input: &str) -> JsTokenArray {
-    let tokens = self.word_dict.get().await.tokenize(input);
+  #[wasm_bindgen(js_name = "createSnapshot")]
+  pub async fn create_snapshot(&self, kind: SnapshotKind) -> Uint8Array {
+    let data = match kind {
+      SnapshotKind::Word => self.word_dict.get().await.to_snapshot(),
+      SnapshotKind::Character => self.character_dict.get().await.to_snapshot(),
+      SnapshotKind::Frequency => self.frequency_dict.get().await.to_snapshot(),
+      SnapshotKind::Sentence => self.sentences_dict.get().await.to_snapshot(),
+      SnapshotKind::Thesaurus => self.thesaurus_dict.get().await.to_snapshot(),
+    };
 
-    JsValue::from(tokens.iter().map(JsToken::from).collect::<Array>()).into()
+    Uint8Array::from(&data[..])
+  }
+
+  pub async fn tokenize(
+    &self,
+    input: &str,
+    statistical: Option<bool>,
+    unit: Option<SpanUnit>,
+  ) -> JsTokenArray {
+    let unit = unit.unwrap_or(SpanUnit::Utf16);
+    let word_dict = self.word_dict.get().await;
+    let tokens = if statistical.unwrap_or(false) {
+      let frequency_dict = self.frequency_dict.get().await;
+
+      word_dict
+        .tokenize_with_mode(input, TokenizeMode::Statistical(frequency_dict))
+    } else {
+      word_dict.tokenize(input)
+    };
+
+    JsValue::from(
+      tokens
+        .iter()
+        .map(|token| JsToken::new(token, unit))
+        .collect::<Array>(),
+    )
+    .into()
+  }
+
+  #[wasm_bindgen(js_name = "detectScript")]
+  pub async fn detect_script(&self, input: &str) -> JsScriptDetection {
+    let word_dict = self.word_dict.get().await;
+
+    serde_wasm_bindgen::to_value(&ScriptDetection::detect(input, word_dict))
+      .unwrap_throw()
+      .into()
+  }
+
+  pub async fn convert(
+    &self,
+    input: &str,
+    to: DictionaryType,
+    unit: Option<SpanUnit>,
+  ) -> JsConversion {
+    let unit = unit.unwrap_or(SpanUnit::Utf16);
+    let word_dict = self.word_dict.get().await;
+    let frequency_dict = self.frequency_dict.get().await;
+
+    JsConversion::new(&word_dict.convert(input, to, frequency_dict), unit)
+  }
+
+  #[wasm_bindgen(js_name = "getLattice")]
+  pub async fn get_lattice(
+    &self,
+    input: &str,
+    unit: Option<SpanUnit>,
+  ) -> JsLattice {
+    let unit = unit.unwrap_or(SpanUnit::Utf16);
+    let lattice = self.word_dict.get().await.lattice(input);
+
+    JsValue::from(
+      lattice
+        .iter()
+        .map(|tokens| {
+          JsValue::from(
+            tokens
+              .iter()
+              .map(|token| JsToken::new(token, unit))
+              .collect::<Array>(),
+          )
+        })
+        .collect::<Array>(),
+    )
+    .into()
+  }
+
+  #[wasm_bindgen(js_name = "getSegmentations")]
+  pub async fn get_segmentations(
+    &self,
+    input: &str,
+    limit: usize,
+    unit: Option<SpanUnit>,
+  ) -> JsSegmentationArray {
+    let unit = unit.unwrap_or(SpanUnit::Utf16);
+    let word_dict = self.word_dict.get().await;
+    let frequency_dict = self.frequency_dict.get().await;
+    let segmentations = word_dict.segmentations(input, frequency_dict, limit);
+
+    JsValue::from(
+      segmentations
+        .iter()
+        .map(|segmentation| JsSegmentation::new(segmentation, unit))
+        .collect::<Array>(),
+    )
+    .into()
+  }
+
+  pub async fn scan(
+    &self,
+    input: &str,
+    unit: Option<SpanUnit>,
+  ) -> JsWordMatchArray {
+    let unit = unit.unwrap_or(SpanUnit::Utf16);
+    let words = self.word_scanner.get().await.scan(input);
+
+    JsValue::from(
+      words
+        .iter()
+        .map(|word| JsWordMatch::new(word, unit))
+        .collect::<Array>(),
+    )
+    .into()
   }
 
   #[wasm_bindgen(js_name = "getWord")]
   pub async fn get_word(
     &self,
     word: &str,
-    simplified: bool,
+    dictionary_mode: Option<DictionaryMode>,
+    format: Option<PinyinFormat>,
   ) -> JsWordEntryArray {
+    let ty = self.dictionary_type(word, dictionary_mode).await;
+    let format = format.unwrap_or(PinyinFormat::Numbered);
+
     JsValue::from(
       self
         .word_dict
         .get()
         .await
-        .get(
-          word,
-          if simplified {
-            DictionaryType::Simplified
-          } else {
-            DictionaryType::Traditional
-          },
-        )
-        .map(|entries| entries.iter().map(JsWordEntry::from).collect::<Array>())
+        .get(word, ty)
+        .map(|entries| {
+          entries
+            .iter()
+            .map(|entry| JsWordEntry::from(&*entry.with_pinyin_format(format)))
+            .collect::<Array>()
+        })
+        .unwrap_or_default(),
+    )
+    .into()
+  }
+
+  #[wasm_bindgen(js_name = "getSenses")]
+  pub async fn get_senses(
+    &self,
+    word: &str,
+    dictionary_mode: Option<DictionaryMode>,
+    format: Option<PinyinFormat>,
+  ) -> JsWordEntrySensesArray {
+    let ty = self.dictionary_type(word, dictionary_mode).await;
+    let format = format.unwrap_or(PinyinFormat::Numbered);
+    let word_dict = self.word_dict.get().await;
+
+    JsValue::from(
+      word_dict
+        .get(word, ty)
+        .map(|entries| {
+          entries
+            .iter()
+            .map(|entry| {
+              let senses = word_dict
+                .get_senses(entry)
+                .into_iter()
+                .map(|sense| sense.with_pinyin_format(format))
+                .collect::<Vec<_>>();
+
+              serde_wasm_bindgen::to_value(&(
+                entry.with_pinyin_format(format),
+                senses,
+              ))
+              .unwrap_throw()
+            })
+            .collect::<Array>()
+        })
         .unwrap_or_default(),
     )
     .into()
   }
 
+  #[wasm_bindgen(js_name = "getClassifiers")]
+  pub async fn get_classifiers(
+    &self,
+    word: &str,
+    dictionary_mode: Option<DictionaryMode>,
+    format: Option<PinyinFormat>,
+  ) -> JsWordLinkArray {
+    let ty = self.dictionary_type(word, dictionary_mode).await;
+    let format = format.unwrap_or(PinyinFormat::Numbered);
+
+    JsValue::from(
+      self
+        .word_dict
+        .get()
+        .await
+        .get_classifiers(word, ty)
+        .into_iter()
+        .map(|link| {
+          serde_wasm_bindgen::to_value(&link.with_pinyin_format(format))
+            .unwrap_throw()
+        })
+        .collect::<Array>(),
+    )
+    .into()
+  }
+
+  #[wasm_bindgen(js_name = "getClassifiedWords")]
+  pub async fn get_classified_words(
+    &self,
+    classifier: &str,
+    limit: usize,
+    dictionary_mode: Option<DictionaryMode>,
+    format: Option<PinyinFormat>,
+  ) -> JsWordEntryArray {
+    let ty = self.dictionary_type(classifier, dictionary_mode).await;
+    let format = format.unwrap_or(PinyinFormat::Numbered);
+    let word_dictionary = self.word_dict.get().await;
+    let frequency_dictionary = self.frequency_dict.get().await;
+
+    JsValue::from(
+      word_dictionary
+        .get_classified_words(classifier, ty, frequency_dictionary)
+        .into_iter()
+        .take(limit)
+        .map(|entry| JsWordEntry::from(&*entry.with_pinyin_format(format)))
+        .collect::<Array>(),
+    )
+    .into()
+  }
+
+  #[wasm_bindgen(js_name = "searchPinyin")]
+  pub async fn search_pinyin(
+    &self,
+    query: &str,
+    limit: usize,
+    format: Option<PinyinFormat>,
+  ) -> JsWordEntryArray {
+    let format = format.unwrap_or(PinyinFormat::Numbered);
+    let word_dictionary = self.word_dict.get().await;
+    let frequency_dictionary = self.frequency_dict.get().await;
+
+    JsValue::from(
+      word_dictionary
+        .search_pinyin(query, frequency_dictionary)
+        .into_iter()
+        .take(limit)
+        .map(|entry| JsWordEntry::from(&*entry.with_pinyin_format(format)))
+        .collect::<Array>(),
+    )
+    .into()
+  }
+
+  #[wasm_bindgen(js_name = "searchJyutping")]
+  pub async fn search_jyutping(
+    &self,
+    query: &str,
+    limit: usize,
+    format: Option<PinyinFormat>,
+  ) -> JsWordEntryArray {
+    let format = format.unwrap_or(PinyinFormat::Numbered);
+    let word_dictionary = self.word_dict.get().await;
+    let frequency_dictionary = self.frequency_dict.get().await;
+
+    JsValue::from(
+      word_dictionary
+        .search_jyutping(query, frequency_dictionary)
+        .into_iter()
+        .take(limit)
+        .map(|entry| JsWordEntry::from(&*entry.with_pinyin_format(format)))
+        .collect::<Array>(),
+    )
+    .into()
+  }
+
+  #[wasm_bindgen(js_name = "searchEnglish")]
+  pub async fn search_english(
+    &self,
+    query: &str,
+    limit: usize,
+    format: Option<PinyinFormat>,
+  ) -> JsWordEntryArray {
+    let format = format.unwrap_or(PinyinFormat::Numbered);
+    let english_index = self.english_index.get().await;
+    let frequency_dictionary = self.frequency_dict.get().await;
+
+    JsValue::from(
+      english_index
+        .search(query, frequency_dictionary)
+        .into_iter()
+        .take(limit)
+        .map(|entry| JsWordEntry::from(&*entry.with_pinyin_format(format)))
+        .collect::<Array>(),
+    )
+    .into()
+  }
+
+  #[wasm_bindgen(js_name = "getSuggestions")]
+  pub async fn get_suggestions(
+    &self,
+    word: &str,
+    limit: usize,
+    dictionary_mode: Option<DictionaryMode>,
+    format: Option<PinyinFormat>,
+  ) -> JsWordEntryDistanceArray {
+    let ty = self.dictionary_type(word, dictionary_mode).await;
+    let format = format.unwrap_or(PinyinFormat::Numbered);
+    let frequency_dictionary = self.frequency_dict.get().await;
+
+    let mut result = self.word_dict.get().await.get_suggestions(word, 1, ty);
+
+    result.sort_by_cached_key(|(entry, distance)| {
+      (
+        *distance,
+        Reverse(
+          frequency_dictionary
+            .get(if ty == DictionaryType::Simplified {
+              &entry.simplified
+            } else {
+              &entry.traditional
+            })
+            .unwrap_or(0),
+        ),
+      )
+    });
+
+    JsValue::from(
+      result
+        .into_iter()
+        .take(limit)
+        .map(|(entry, distance)| {
+          serde_wasm_bindgen::to_value(&(
+            entry.with_pinyin_format(format),
+            distance,
+          ))
+          .unwrap_throw()
+        })
+        .collect::<Array>(),
+    )
+    .into()
+  }
+
   #[wasm_bindgen(js_name = "getWordsIncludingSubslice")]
   pub async fn get_words_including_subslice(
     &self,
     slice: &str,
     limit: usize,
-    simplified: bool,
+    dictionary_mode: Option<DictionaryMode>,
+    format: Option<PinyinFormat>,
   ) -> JsWordEntryArray {
+    let ty = self.dictionary_type(slice, dictionary_mode).await;
+    let format = format.unwrap_or(PinyinFormat::Numbered);
     let character_dictionary = self.character_dict.get().await;
 
     let mut result = self
       .word_dict
       .get()
       .await
-      .iter_including_subslice(
-        slice,
-        if simplified {
-          DictionaryType::Simplified
-        } else {
-          DictionaryType::Traditional
-        },
-      )
+      .iter_including_subslice(slice, ty)
       .collect::<Vec<_>>();
 
     result.sort_by_cached_key(|entry| {
       character_dictionary
-        .stroke_count(if simplified {
+        .stroke_count(if ty == DictionaryType::Simplified {
           &entry.simplified
         } else {
           &entry.traditional
@@ -321,7 +1164,50 @@ impl Worker {
       result
         .into_iter()
         .take(limit)
-        .map(JsWordEntry::from)
+        .map(|entry| JsWordEntry::from(&*entry.with_pinyin_format(format)))
+        .collect::<Array>(),
+    )
+    .into()
+  }
+
+  #[wasm_bindgen(js_name = "getWordsMatchingPattern")]
+  pub async fn get_words_matching_pattern(
+    &self,
+    pattern: &str,
+    limit: usize,
+    dictionary_mode: Option<DictionaryMode>,
+    format: Option<PinyinFormat>,
+  ) -> JsWordEntryArray {
+    let ty = self.dictionary_type(pattern, dictionary_mode).await;
+    let format = format.unwrap_or(PinyinFormat::Numbered);
+    let word_dictionary = self.word_dict.get().await;
+    let character_dictionary = self.character_dict.get().await;
+
+    let mut result = WordPattern::parse(pattern)
+      .map(|pattern| {
+        word_dictionary
+          .iter_matching(&pattern, ty, |character, component| {
+            character_dictionary.has_component(character, component)
+          })
+          .collect::<Vec<_>>()
+      })
+      .unwrap_or_default();
+
+    result.sort_by_cached_key(|entry| {
+      character_dictionary
+        .stroke_count(if ty == DictionaryType::Simplified {
+          &entry.simplified
+        } else {
+          &entry.traditional
+        })
+        .unwrap_or(usize::MAX)
+    });
+
+    JsValue::from(
+      result
+        .into_iter()
+        .take(limit)
+        .map(|entry| JsWordEntry::from(&*entry.with_pinyin_format(format)))
         .collect::<Array>(),
     )
     .into()
@@ -331,29 +1217,32 @@ impl Worker {
   pub async fn get_homophones(
     &self,
     word: &str,
-    simplified: bool,
-  ) -> JsWordEntryExactArray {
+    dictionary_mode: Option<DictionaryMode>,
+    format: Option<PinyinFormat>,
+    mode: Option<HomophoneMode>,
+    jyutping: Option<bool>,
+  ) -> JsWordEntryHomophoneModeArray {
+    let ty = self.dictionary_type(word, dictionary_mode).await;
+    let format = format.unwrap_or(PinyinFormat::Numbered);
+    let mode = mode.unwrap_or(HomophoneMode::ToneInsensitive);
+    let word_dictionary = self.word_dict.get().await;
     let character_dictionary = self.character_dict.get().await;
 
-    let mut result = self
-      .word_dict
-      .get()
-      .await
-      .iter_homophones(
-        word,
-        if simplified {
-          DictionaryType::Simplified
-        } else {
-          DictionaryType::Traditional
-        },
-      )
-      .collect::<Vec<_>>();
+    let mut result = if jyutping.unwrap_or(false) {
+      word_dictionary
+        .iter_jyutping_homophones(word, ty, mode)
+        .collect::<Vec<_>>()
+    } else {
+      word_dictionary
+        .iter_homophones(word, ty, mode)
+        .collect::<Vec<_>>()
+    };
 
-    result.sort_by_cached_key(|(entry, exact)| {
+    result.sort_by_cached_key(|(entry, mode)| {
       (
-        !*exact,
+        *mode,
         character_dictionary
-          .stroke_count(if simplified {
+          .stroke_count(if ty == DictionaryType::Simplified {
             &*entry.simplified
           } else {
             &*entry.traditional
@@ -365,14 +1254,63 @@ impl Worker {
     JsValue::from(
       result
         .into_iter()
-        .map(|(entry, exact)| {
-          serde_wasm_bindgen::to_value(&(entry, exact)).unwrap_throw()
+        .map(|(entry, mode)| {
+          serde_wasm_bindgen::to_value(&(
+            entry.with_pinyin_format(format),
+            mode,
+          ))
+          .unwrap_throw()
         })
         .collect::<Array>(),
     )
     .into()
   }
 
+  #[wasm_bindgen(js_name = "queryPinyin")]
+  pub async fn query_pinyin(
+    &self,
+    rhyme: Option<String>,
+    tones: Option<String>,
+    syllables: Option<usize>,
+    limit: usize,
+    format: Option<PinyinFormat>,
+  ) -> JsWordEntryArray {
+    let format = format.unwrap_or(PinyinFormat::Numbered);
+    let word_dictionary = self.word_dict.get().await;
+    let frequency_dictionary = self.frequency_dict.get().await;
+
+    // Invalid rhymes or tones match nothing
+
+    let query = (|| {
+      let rhyme = match rhyme.as_deref() {
+        Some(rhyme) => Some(PinyinQuery::parse_rhyme(rhyme)?),
+        None => None,
+      };
+      let tones = match tones.as_deref() {
+        Some(tones) => Some(PinyinQuery::parse_tones(tones)?),
+        None => None,
+      };
+
+      Some(PinyinQuery {
+        final_: rhyme.map(|(final_, _)| final_),
+        tone: rhyme.and_then(|(_, tone)| tone),
+        tones,
+        syllables,
+      })
+    })();
+
+    JsValue::from(
+      query
+        .map(|query| word_dictionary.query_pinyin(&query, frequency_dictionary))
+        .unwrap_or_default()
+        .into_iter()
+        .take(limit)
+        .map(|entry| JsWordEntry::from(&*entry.with_pinyin_format(format)))
+        .collect::<Array>(),
+    )
+    .into()
+  }
+
   #[wasm_bindgen(js_name = "getCharacter")]
   pub async fn get_character(
     &self,
@@ -390,8 +1328,11 @@ impl Worker {
   pub async fn get_characters_including_component(
     &self,
     component: char,
-    simplified: bool,
+    dictionary_mode: Option<DictionaryMode>,
   ) -> JsCharacterEntryArray {
+    let ty = self
+      .dictionary_type(&component.to_string(), dictionary_mode)
+      .await;
     let word_dictionary = self.word_dict.get().await;
     let character_dictionary = self.character_dict.get().await;
 
@@ -399,14 +1340,7 @@ impl Worker {
       .get_characters_including_component(component)
       .filter(|entry| {
         word_dictionary
-          .get(
-            &entry.character.to_string(),
-            if simplified {
-              DictionaryType::Simplified
-            } else {
-              DictionaryType::Traditional
-            },
-          )
+          .get(&entry.character.to_string(), ty)
           .is_some()
       })
       .collect::<Vec<_>>();
@@ -470,20 +1404,14 @@ impl Worker {
     &self,
     word: &str,
     limit: usize,
-    simplified: bool,
+    dictionary_mode: Option<DictionaryMode>,
   ) -> JsSentenceArray {
+    let ty = self.dictionary_type(word, dictionary_mode).await;
     let mut sentences = self
       .sentences_dict
       .get()
       .await
-      .iter_sentences_including_word(
-        word,
-        if simplified {
-          DictionaryType::Simplified
-        } else {
-          DictionaryType::Traditional
-        },
-      )
+      .iter_sentences_including_word(word, ty)
       .collect::<Vec<_>>();
 
     sentences.sort_by_key(|(sentence, _)| sentence.len());
@@ -503,21 +1431,16 @@ impl Worker {
     &self,
     word: &str,
     limit: usize,
-    simplified: bool,
+    dictionary_mode: Option<DictionaryMode>,
   ) -> JsWordScoreArray {
+    let ty = self.dictionary_type(word, dictionary_mode).await;
+
     JsValue::from(
       self
         .thesaurus_dict
         .get()
         .await
-        .get_similar_words(
-          word,
-          if simplified {
-            DictionaryType::Simplified
-          } else {
-            DictionaryType::Traditional
-          },
-        )
+        .get_similar_words(word, ty)
         .into_iter()
         .take(limit)
         .map(|(word, score)| {
@@ -528,3 +1451,79 @@ impl Worker {
     .into()
   }
 }
+
+#[cfg(test)]
+mod tests {
+  use once_cell::sync::Lazy;
+
+  use super::{
+    load_thesaurus, overlay_user_dict, DictionaryData, UserDictionaryData,
+  };
+  use crate::{
+    DictionaryType, OverlayMode, SentenceDictionary, SnapshotError,
+    SnapshotKind, ThesaurusDictionary, WordDictionary,
+  };
+
+  static CEDICT_DATA: Lazy<WordDictionary> = Lazy::new(|| {
+    WordDictionary::new(include_str!(
+      "../../../data/cedict_1_0_ts_utf-8_mdbg.txt"
+    ))
+  });
+
+  #[test]
+  fn should_build_thesaurus_when_sentences_are_a_snapshot() {
+    let sentences = SentenceDictionary::new(
+      include_str!("../../../data/sentences.txt"),
+      &CEDICT_DATA,
+    )
+    .to_snapshot();
+    let similar_words = |dict: &ThesaurusDictionary| {
+      dict
+        .get_similar_words("人民", DictionaryType::Simplified)
+        .into_iter()
+        .map(|(word, score)| (word.to_string(), score))
+        .collect::<Vec<_>>()
+    };
+
+    assert!(SentenceDictionary::from_snapshot(&sentences).is_ok());
+    assert_eq!(
+      load_thesaurus(Some(DictionaryData::Snapshot(sentences)), &CEDICT_DATA)
+        .err(),
+      Some(SnapshotError::WrongKind(SnapshotKind::Sentence as u8))
+    );
+
+    let dict = load_thesaurus(None, &CEDICT_DATA).unwrap();
+
+    assert!(!similar_words(&dict).is_empty());
+    assert_eq!(
+      similar_words(&dict),
+      similar_words(&ThesaurusDictionary::new(&CEDICT_DATA))
+    );
+  }
+
+  #[test]
+  fn should_not_overlay_user_dicts_of_snapshots_again() {
+    let user_dict = UserDictionaryData {
+      name: "glossary".to_string(),
+      data: "頭髮 头发 [tou2 fa4] /hairdo/".to_string(),
+      priority: None,
+      mode: OverlayMode::Append,
+      readings_only: false,
+    };
+    let mut dict = WordDictionary::new("頭髮 头发 [tou2 fa5] /hair/");
+
+    assert!(overlay_user_dict(&mut dict, &user_dict).is_some());
+
+    let mut snapshot =
+      WordDictionary::from_snapshot(&dict.to_snapshot()).unwrap();
+
+    assert!(overlay_user_dict(&mut snapshot, &user_dict).is_none());
+    assert_eq!(snapshot.sources(), dict.sources());
+    assert_eq!(
+      snapshot
+        .get("头发", DictionaryType::Simplified)
+        .map(Vec::len),
+      Some(2)
+    );
+  }
+}
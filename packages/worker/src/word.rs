@@ -1,338 +1,3211 @@
+use rustc_hash::{FxHashMap as HashMap, FxHashSet as HashSet};
 use serde::Serialize;
-use std::sync::Arc;
+use std::{borrow::Cow, cmp::Reverse, mem, sync::Arc};
+use wasm_bindgen::prelude::wasm_bindgen;
 
-use crate::trie::Trie;
+use crate::{
+  parse_senses,
+  trie::{KeyElement, Trie, TrieKey},
+  DictionarySource, FrequencyDictionary, JyutpingSyllable, OverlayMode,
+  ParseErrorKind, ParseReport, Pinyin, PinyinFormat, PinyinQuery, Sense,
+  SnapshotError, SnapshotKind, SnapshotReader, SnapshotWriter, Syllable,
+  WordLink, WordPattern,
+};
 
-pub const CHINESE_PUNCTUATION: &'static [char] = &[
-  '·', '×', '—', '‘', '’', '“', '”', '…', '、', '。', '《', '》', '『', '』',
-  '【', '】', '！', '（', '）', '，', '：', '；', '？',
-];
+/// Penalty in log10 units for a single character word of a segmentation,
+/// halved for every further character. With frequency data that only lists
+/// characters, such as SUBTLEX-CH-CHR, every word is estimated from its
+/// characters and all segmentations would score alike, so this makes fewer
+/// and more evenly sized words win instead.
+const TOKEN_PENALTY: f32 = 1.0;
 
-#[derive(Debug, PartialEq, Eq, Clone, Serialize)]
+/// Number of u32 fields of an entry in a snapshot, one string id for each
+/// field of [`WordEntry`].
+const SNAPSHOT_ENTRY_LEN: usize = 6;
+
+/// Stands for a missing string in the entries of a snapshot.
+const SNAPSHOT_NONE: u32 = u32::MAX;
+
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Serialize)]
 pub struct WordEntry {
   pub traditional: Arc<str>,
   pub simplified: Arc<str>,
   pub pinyin: Arc<str>,
+  /// Cantonese reading, e.g. `cyun4 tung2`, if known
+  pub jyutping: Option<Arc<str>>,
   pub english: Arc<str>,
+  /// Name of the source the entry comes from, see [`DictionarySource`]
+  pub source: Arc<str>,
+}
+
+impl WordEntry {
+  pub fn parse_pinyin(&self) -> Pinyin {
+    Pinyin::parse(&self.pinyin)
+  }
+
+  /// Returns the entry with its pinyin rendered in `format`. Numbered pinyin
+  /// is kept as is.
+  pub fn with_pinyin_format(&self, format: PinyinFormat) -> Cow<'_, WordEntry> {
+    match format {
+      PinyinFormat::Numbered => Cow::Borrowed(self),
+      format => Cow::Owned(WordEntry {
+        pinyin: self.parse_pinyin().format(format).into(),
+        ..self.clone()
+      }),
+    }
+  }
+
+  fn is_same_reading(&self, other: &WordEntry) -> bool {
+    self.traditional == other.traditional
+      && self.simplified == other.simplified
+      && self.pinyin == other.pinyin
+  }
+}
+
+/// Whether an entry only points to another word, i.e. all of its senses are
+/// variants of other words.
+fn is_variant_entry(entry: &WordEntry) -> bool {
+  let senses = parse_senses(&entry.english);
+
+  !senses.is_empty()
+    && senses.iter().all(|sense| {
+      matches!(sense, Sense::VariantOf { .. } | Sense::OldVariantOf { .. })
+    })
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum TokenKind {
+  /// A word with dictionary entries
+  ChineseWord,
+  /// A Chinese character without dictionary entries
+  UnknownHanzi,
+  Punctuation,
+  Whitespace,
+  /// Latin or other alphabetic text
+  Latin,
+  Number,
+  Symbol,
+}
+
+impl TokenKind {
+  /// Classifies a single character regardless of dictionary entries, i.e.
+  /// Chinese characters are always `UnknownHanzi`.
+  pub fn of_char(ch: char) -> Self {
+    match ch as u32 {
+      // Full-width forms of ASCII characters are classified like them
+      0xff01..=0xff5e => {
+        Self::of_char(char::from_u32(ch as u32 - 0xfee0).unwrap_or_default())
+      }
+      _ if ch.is_whitespace() => TokenKind::Whitespace,
+      _ if is_hanzi(ch) => TokenKind::UnknownHanzi,
+      _ if is_punctuation(ch) => TokenKind::Punctuation,
+      _ if ch.is_numeric() => TokenKind::Number,
+      _ if ch.is_alphabetic() => TokenKind::Latin,
+      _ => TokenKind::Symbol,
+    }
+  }
+}
+
+fn is_hanzi(ch: char) -> bool {
+  matches!(
+    ch as u32,
+    // CJK Radicals Supplement, Kangxi Radicals
+    0x2e80..=0x2fdf
+      // Iteration marks and ideographic numbers
+      | 0x3005 | 0x3007 | 0x3021..=0x3029 | 0x3038..=0x303b
+      // CJK Unified Ideographs Extension A, CJK Unified Ideographs
+      | 0x3400..=0x4dbf | 0x4e00..=0x9fff
+      // CJK Compatibility Ideographs
+      | 0xf900..=0xfaff
+      // CJK Unified Ideographs Extension B to I and supplements
+      | 0x20000..=0x323af
+  )
+}
+
+fn is_punctuation(ch: char) -> bool {
+  match ch as u32 {
+    // ASCII punctuation which is not a symbol
+    0x00..=0x7f => ch.is_ascii_punctuation() && !"$+<=>^`|~".contains(ch),
+    // Latin-1 punctuation, e.g. inverted marks and guillemets
+    0xa1 | 0xa7 | 0xab | 0xb6 | 0xb7 | 0xbb | 0xbf => true,
+    // General Punctuation, e.g. dashes, quotation marks and ellipses
+    0x2010..=0x2027 | 0x2030..=0x205e => true,
+    // CJK Symbols and Punctuation except iteration marks and numbers
+    0x3001..=0x303f => !is_hanzi(ch),
+    // Katakana middle dot
+    0x30fb => true,
+    // Vertical Forms, CJK Compatibility Forms, Small Form Variants
+    0xfe10..=0xfe19 | 0xfe30..=0xfe6b => true,
+    // Full-width brackets and half-width CJK punctuation
+    0xff5f..=0xff65 => true,
+    _ => false,
+  }
+}
+
+/// A half-open range of offsets into the input.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub struct Span {
+  pub start: usize,
+  pub end: usize,
+}
+
+#[wasm_bindgen]
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum SpanUnit {
+  /// Bytes of UTF-8, e.g. for slicing Rust strings
+  Byte = 1,
+  /// Unicode scalar values
+  Char = 2,
+  /// UTF-16 code units, e.g. for slicing JavaScript strings
+  Utf16 = 3,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+struct Position {
+  byte: usize,
+  char: usize,
+  utf16: usize,
+}
+
+impl Position {
+  fn advance(self, value: &str) -> Self {
+    Self {
+      byte: self.byte + value.len(),
+      char: self.char + value.chars().count(),
+      utf16: self.utf16 + value.encode_utf16().count(),
+    }
+  }
 }
 
-#[derive(Debug, PartialEq, Eq, Clone, Serialize)]
+#[derive(Debug, PartialEq, Eq, Clone)]
 pub struct Token {
   pub value: Arc<str>,
-  pub offset: usize,
-  #[serde(rename = "hasEntries")]
   pub has_entries: bool,
+  pub kind: TokenKind,
+  pub byte_span: Span,
+  pub char_span: Span,
+  pub utf16_span: Span,
+}
+
+impl Token {
+  pub fn span(&self, unit: SpanUnit) -> Span {
+    match unit {
+      SpanUnit::Byte => self.byte_span,
+      SpanUnit::Char => self.char_span,
+      SpanUnit::Utf16 => self.utf16_span,
+    }
+  }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct Segmentation {
+  pub tokens: Vec<Token>,
+  /// Log probability of the segmentation with base 10
+  pub score: f32,
+}
+
+/// A word with several possible conversions into the other script.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Ambiguity {
+  /// The word in the input
+  pub source: Token,
+  /// The chosen conversion in the output
+  pub target: Token,
+  /// All possible conversions, the chosen one first
+  pub candidates: Vec<Arc<str>>,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct Conversion {
+  pub text: String,
+  pub ambiguities: Vec<Ambiguity>,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum TokenizeMode<'a> {
+  /// Always takes the longest dictionary word
+  Greedy,
+  /// Takes the most probable sequence of words according to the given
+  /// frequencies
+  Statistical(&'a FrequencyDictionary),
+}
+
+/// How closely homophones need to match, from strictest to loosest.
+#[wasm_bindgen]
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum HomophoneMode {
+  /// Same syllables and tones
+  Exact = 1,
+  /// Same syllables in any tones
+  ToneInsensitive = 2,
+  /// Syllables with confusable initials or finals in any tones, see
+  /// [`Syllable::is_confusable_with`]
+  Confusable = 3,
 }
 
+#[wasm_bindgen]
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
 pub enum DictionaryType {
-  Simplified,
-  Traditional,
+  Simplified = 1,
+  Traditional = 2,
+}
+
+/// The fields of a line in CEDICT format, see [`WordDictionary::parse_line`]
+struct LineFields<'a> {
+  traditional: &'a str,
+  simplified: &'a str,
+  pinyin: String,
+  jyutping: Option<String>,
+  english: String,
+}
+
+/// A character of the other script a character converts to, see
+/// [`WordDictionary::convert`].
+#[derive(Debug, Clone)]
+struct CharConversion {
+  target: char,
+  /// Number of entries consisting of the character alone
+  whole_words: usize,
+  /// Simplified forms of all words converting the character this way
+  words: Vec<Arc<str>>,
+}
+
+impl CharConversion {
+  /// Returns the summed probability of all words converting the character
+  /// this way that `frequency_dict` has data for.
+  fn weight(&self, frequency_dict: &FrequencyDictionary) -> f64 {
+    let mut seen = HashSet::default();
+
+    self
+      .words
+      .iter()
+      .filter(|word| seen.insert(&***word) && frequency_dict.contains(word))
+      .map(|word| 10_f64.powf(frequency_dict.log_probability(word) as f64))
+      .sum()
+  }
 }
 
 #[derive(Debug, Clone)]
 pub struct WordDictionary {
   simplified: Trie<Vec<WordEntry>>,
   traditional: Trie<Vec<WordEntry>>,
+  pinyin: Trie<Vec<WordEntry>, String>,
+  jyutping: Trie<Vec<WordEntry>, String>,
+  /// Maps simplified classifiers to the entries using them
+  simplified_classified: HashMap<Arc<str>, Vec<WordEntry>>,
+  /// Maps traditional classifiers to the entries using them
+  traditional_classified: HashMap<Arc<str>, Vec<WordEntry>>,
+  /// Maps simplified characters to their traditional conversions
+  simplified_conversions: HashMap<char, Vec<CharConversion>>,
+  /// Maps traditional characters to their simplified conversions
+  traditional_conversions: HashMap<char, Vec<CharConversion>>,
+  /// Parsed forms of the syllables in the pinyin index for finding
+  /// homophones
+  pinyin_syllables: HashMap<String, Syllable>,
+  /// Parsed forms of the syllables in the Jyutping index
+  jyutping_syllables: HashMap<String, JyutpingSyllable>,
+  /// All sources in the order they were added
+  sources: Vec<DictionarySource>,
 }
 
 impl WordDictionary {
   pub fn new(data: &str) -> Self {
+    Self::parse_with_report(data).0
+  }
+
+  /// Parses CEDICT data like [`WordDictionary::new`], but also reports
+  /// malformed lines instead of silently skipping them.
+  pub fn parse_with_report(data: &str) -> (Self, ParseReport) {
+    let source = DictionarySource::base();
     let mut result = Self {
-      simplified: Trie::with_capacity(16_384),
-      traditional: Trie::with_capacity(16_384),
+      simplified: Trie::new(),
+      traditional: Trie::new(),
+      pinyin: Trie::new(),
+      jyutping: Trie::new(),
+      simplified_classified: HashMap::default(),
+      traditional_classified: HashMap::default(),
+      simplified_conversions: HashMap::default(),
+      traditional_conversions: HashMap::default(),
+      pinyin_syllables: HashMap::default(),
+      jyutping_syllables: HashMap::default(),
+      sources: vec![source.clone()],
     };
+    let mut report = ParseReport::default();
 
-    for line in data.lines() {
+    for (i, line) in data.lines().enumerate() {
       let line = line.trim();
 
       if line.is_empty() || line.starts_with("#") {
         continue;
       }
 
-      let mut tokens = line.split_ascii_whitespace();
-      let traditional = tokens.next();
-      let simplified = tokens.next();
+      let Some(entry) = report.record(i, Self::parse_line(line, &source.name))
+      else {
+        continue;
+      };
 
-      let pinyin = {
-        let mut result = String::new();
+      result.insert_classified(&entry);
+      result.insert_jyutping(&entry);
+      result.insert_conversions(&entry);
+      result
+        .simplified
+        .get_mut_or_insert(&*entry.simplified, || Vec::with_capacity(1))
+        .map(|vec| vec.push(entry.clone()));
+      result
+        .traditional
+        .get_mut_or_insert(&*entry.traditional, || Vec::with_capacity(1))
+        .map(|vec| vec.push(entry.clone()));
 
-        while let Some(token) = tokens.next() {
-          result += token;
+      let pinyin = Self::pinyin_key(&entry.pinyin);
 
-          if token.ends_with("]") {
-            break;
-          } else {
-            result.push(' ');
-          }
-        }
+      Self::insert_syllables(
+        &mut result.pinyin_syllables,
+        &pinyin,
+        Syllable::parse,
+      );
 
-        result
-      };
-      let pinyin = &pinyin[1..pinyin.len() - 1];
+      if let Some(vec) = result
+        .pinyin
+        .get_mut_or_insert(&pinyin, || Vec::with_capacity(1))
+      {
+        vec.push(entry);
+      }
+    }
 
-      let english = tokens.collect::<Vec<_>>().join(" ");
-      let english = english.trim();
-      let english = &english[1..english.len() - 1];
+    (result, report)
+  }
 
-      if let (Some(traditional), Some(simplified)) = (traditional, simplified) {
-        if traditional.is_ascii() || simplified.is_ascii() {
-          continue;
-        }
+  /// Parses a line of the form `傳統 传统 [chuan2 tong3] /tradition/`,
+  /// optionally with Jyutping in braces after the pinyin as in CC-Canto, e.g.
+  /// `傳統 传统 [chuan2 tong3] {cyun4 tung2} /tradition/`. Entries of words
+  /// without Chinese characters are skipped.
+  fn parse_line(
+    line: &str,
+    source: &Arc<str>,
+  ) -> Result<Option<WordEntry>, ParseErrorKind> {
+    let fields = Self::parse_fields(line)?;
 
-        let entry = WordEntry {
-          traditional: traditional.into(),
-          simplified: simplified.into(),
-          pinyin: pinyin.into(),
-          english: english.into(),
-        };
+    if fields.english.is_empty() {
+      return Err(ParseErrorKind::MissingField("definition"));
+    }
 
-        result
-          .simplified
-          .get_mut_or_insert(simplified, || Vec::with_capacity(1))
-          .map(|vec| vec.push(entry.clone()));
-        result
-          .traditional
-          .get_mut_or_insert(traditional, || Vec::with_capacity(1))
-          .map(|vec| vec.push(entry));
-      }
+    let english = fields
+      .english
+      .strip_prefix('/')
+      .and_then(|english| english.strip_suffix('/'))
+      .ok_or(ParseErrorKind::InvalidDefinition)?;
+
+    if english.split('/').all(|sense| sense.trim().is_empty()) {
+      return Err(ParseErrorKind::EmptyDefinition);
     }
 
-    result
+    if fields.traditional.is_ascii() || fields.simplified.is_ascii() {
+      return Ok(None);
+    }
+
+    Ok(Some(WordEntry {
+      traditional: fields.traditional.into(),
+      simplified: fields.simplified.into(),
+      pinyin: fields.pinyin.into(),
+      jyutping: fields.jyutping.map(Into::into),
+      english: english.into(),
+      source: source.clone(),
+    }))
   }
 
-  pub fn get(&self, word: &str, ty: DictionaryType) -> Option<&Vec<WordEntry>> {
-    match ty {
-      DictionaryType::Simplified => &self.simplified,
-      DictionaryType::Traditional => &self.traditional,
+  /// Splits a line into its fields without checking the definition, which
+  /// is missing in files only listing readings.
+  fn parse_fields(line: &str) -> Result<LineFields<'_>, ParseErrorKind> {
+    let mut tokens = line.split_ascii_whitespace().peekable();
+    let traditional = tokens
+      .next()
+      .ok_or(ParseErrorKind::MissingField("traditional"))?;
+    let simplified = tokens
+      .next()
+      .ok_or(ParseErrorKind::MissingField("simplified"))?;
+    let pinyin = Self::take_field(&mut tokens, ']');
+
+    if pinyin.is_empty() {
+      return Err(ParseErrorKind::MissingField("pinyin"));
     }
-    .get(word)
+
+    let pinyin = pinyin
+      .strip_prefix('[')
+      .and_then(|pinyin| pinyin.strip_suffix(']'))
+      .filter(|pinyin| !pinyin.trim().is_empty())
+      .ok_or(ParseErrorKind::InvalidPinyin)?
+      .to_string();
+
+    let jyutping = match tokens.peek() {
+      Some(token) if token.starts_with('{') => Some(
+        Self::take_field(&mut tokens, '}')
+          .strip_prefix('{')
+          .and_then(|jyutping| jyutping.strip_suffix('}'))
+          .filter(|jyutping| !jyutping.trim().is_empty())
+          .ok_or(ParseErrorKind::InvalidJyutping)?
+          .to_string(),
+      ),
+      _ => None,
+    };
+
+    Ok(LineFields {
+      traditional,
+      simplified,
+      pinyin,
+      jyutping,
+      english: tokens.collect::<Vec<_>>().join(" "),
+    })
   }
 
-  pub fn iter_prefix(
-    &self,
-    word: &str,
-    ty: DictionaryType,
-  ) -> impl Iterator<Item = &WordEntry> {
-    match ty {
-      DictionaryType::Simplified => &self.simplified,
-      DictionaryType::Traditional => &self.traditional,
+  /// Joins the tokens of a field spanning several tokens, e.g. `[chuan2` and
+  /// `tong3]`, up to the token ending with `close`.
+  fn take_field<'b>(
+    tokens: &mut impl Iterator<Item = &'b str>,
+    close: char,
+  ) -> String {
+    let mut result = String::new();
+
+    for token in tokens {
+      result += token;
+
+      if token.ends_with(close) {
+        break;
+      } else {
+        result.push(' ');
+      }
     }
-    .iter_prefix(word)
-    .flat_map(|vec| vec.iter())
-  }
 
-  pub fn iter(&self) -> impl Iterator<Item = &WordEntry> {
-    self.iter_prefix("", DictionaryType::Traditional)
+    result
   }
 
-  pub fn iter_including_subslice<'a>(
-    &'a self,
-    slice: &'a str,
-    ty: DictionaryType,
-  ) -> impl Iterator<Item = &'a WordEntry> {
-    self.iter().filter(move |entry| {
-      let word = match ty {
-        DictionaryType::Simplified => &entry.simplified,
-        DictionaryType::Traditional => &entry.traditional,
-      };
+  /// Splits pinyin into normalized, lowercase syllables for the
+  /// syllable-keyed index, so that e.g. `lu:4`, `lv4` and `lü4` are equal.
+  fn pinyin_key(pinyin: &str) -> Vec<String> {
+    Pinyin::parse(pinyin).keys()
+  }
 
-      &**word != slice && word.contains(slice)
-    })
+  /// Splits Jyutping into lowercase syllables for the syllable-keyed index.
+  fn jyutping_key(jyutping: &str) -> Vec<String> {
+    jyutping
+      .split_whitespace()
+      .map(|syllable| syllable.to_lowercase())
+      .collect()
   }
 
-  pub fn iter_homophones<'a>(
-    &'a self,
-    word: &'a str,
-    ty: DictionaryType,
-  ) -> impl Iterator<Item = (&'a WordEntry, bool)> {
-    fn normalize_pinyin(pinyin: &str) -> String {
-      pinyin
-        .to_ascii_lowercase()
-        .replace(|ch: char| ch.is_ascii_digit(), "")
+  fn insert_jyutping(&mut self, entry: &WordEntry) {
+    if let Some(jyutping) = &entry.jyutping {
+      let key = Self::jyutping_key(jyutping);
+
+      Self::insert_syllables(
+        &mut self.jyutping_syllables,
+        &key,
+        JyutpingSyllable::parse,
+      );
+      Self::insert_into(&mut self.jyutping, &key, entry.clone());
     }
+  }
 
-    let entries = self
-      .get(word, ty)
-      .map(|entries| entries.iter())
-      .into_iter()
-      .flatten();
-    let exact_pinyins = entries
-      .clone()
-      .map(|entry| entry.pinyin.to_ascii_lowercase())
-      .collect::<Vec<_>>();
-    let normalized_pinyins = entries
-      .clone()
-      .map(|entry| normalize_pinyin(&entry.pinyin))
-      .collect::<Vec<_>>();
+  /// Adds the parsed forms of the syllables of `key` to `syllables`, unless
+  /// already known. Syllables stay known when their entries get removed.
+  fn insert_syllables<S>(
+    syllables: &mut HashMap<String, S>,
+    key: &[String],
+    parse: impl Fn(&str) -> Option<S>,
+  ) {
+    for syllable in key {
+      if !syllables.contains_key(syllable) {
+        if let Some(parsed) = parse(syllable) {
+          syllables.insert(syllable.clone(), parsed);
+        }
+      }
+    }
+  }
 
-    self
-      .iter()
-      .filter(move |entry| {
-        word
-          != match ty {
-            DictionaryType::Simplified => &*entry.simplified,
-            DictionaryType::Traditional => &*entry.traditional,
-          }
-      })
-      .filter(|entry| !entry.english.contains("variant of"))
-      .filter_map(move |entry| {
-        exact_pinyins
-          .contains(&entry.pinyin.to_ascii_lowercase())
-          .then(|| (entry, true))
-          .or_else(|| {
-            normalized_pinyins
-              .contains(&normalize_pinyin(&entry.pinyin))
-              .then(|| (entry, false))
-          })
-      })
+  fn remove_jyutping(&mut self, entry: &WordEntry) {
+    if let Some(jyutping) = &entry.jyutping {
+      Self::remove_from(
+        &mut self.jyutping,
+        &Self::jyutping_key(jyutping),
+        entry,
+      );
+    }
   }
 
-  pub fn tokenize(&self, input: &str) -> Vec<Token> {
-    let mut chars = input.char_indices().peekable();
-    let mut tokens = vec![];
-    let mut offset = 0;
-
-    let mut push_token = |word: &str| {
-      tokens.push(Token {
-        value: word.into(),
-        offset,
-        has_entries: self.get(word, DictionaryType::Simplified).is_some()
-          || self.get(word, DictionaryType::Traditional).is_some(),
+  /// Writes the dictionary together with all of its indices, so that
+  /// loading it parses nothing. Every entry is written once into a flat
+  /// table of string ids and referred to by its index everywhere else.
+  pub fn to_snapshot(&self) -> Vec<u8> {
+    let mut writer = SnapshotWriter::new();
+    let mut entries = vec![];
+    let mut ids = HashMap::<&WordEntry, u32>::default();
+
+    for entry in self
+      .simplified
+      .iter()
+      .chain(self.traditional.iter())
+      .map(|(_, entries)| entries)
+      .chain(self.pinyin.iter().map(|(_, entries)| entries))
+      .chain(self.jyutping.iter().map(|(_, entries)| entries))
+      .chain(self.simplified_classified.values())
+      .chain(self.traditional_classified.values())
+      .flatten()
+    {
+      ids.entry(entry).or_insert_with(|| {
+        entries.push(entry);
+        entries.len() as u32 - 1
       });
+    }
 
-      offset += word.chars().count();
-    };
+    writer.write_len(entries.len());
 
-    while let Some((i, ch)) = chars.next() {
-      // First, try to match two or more characters
+    for entry in entries {
+      for field in [
+        Some(&entry.traditional),
+        Some(&entry.simplified),
+        Some(&entry.pinyin),
+        entry.jyutping.as_ref(),
+        Some(&entry.english),
+        Some(&entry.source),
+      ] {
+        let id = field.map_or(SNAPSHOT_NONE, |field| writer.intern(field));
 
-      if let Some(&(_, next_ch)) = chars.peek() {
-        let sliced_input = &input[i..];
-        let prefix = [ch, next_ch].into_iter().collect::<String>();
-        let mut found_word = None::<&str>;
+        writer.write_u32(id);
+      }
+    }
 
-        let entries = self
-          .iter_prefix(&prefix, DictionaryType::Simplified)
-          .chain(self.iter_prefix(&prefix, DictionaryType::Traditional));
+    let mut write_entries =
+      |writer: &mut SnapshotWriter, entries: &Vec<WordEntry>| {
+        writer.write_len(entries.len());
 
         for entry in entries {
-          let new_found_word = if sliced_input.starts_with(&*entry.simplified) {
-            Some(&*entry.simplified)
-          } else if sliced_input.starts_with(&*entry.traditional) {
-            Some(&*entry.traditional)
-          } else {
-            None
-          };
-
-          found_word = match (found_word, new_found_word) {
-            (Some(found_word), Some(new_found_word))
-              if found_word.len() < new_found_word.len() =>
-            {
-              Some(new_found_word)
-            }
-            (None, _) => new_found_word,
-            _ => found_word,
-          };
+          writer.write_u32(ids[entry]);
         }
+      };
+
+    self
+      .simplified
+      .write_snapshot(&mut writer, &mut write_entries);
+    self
+      .traditional
+      .write_snapshot(&mut writer, &mut write_entries);
+    self.pinyin.write_snapshot(&mut writer, &mut write_entries);
+    self
+      .jyutping
+      .write_snapshot(&mut writer, &mut write_entries);
 
-        if let Some(found_word) = found_word {
-          push_token(found_word);
+    for map in [&self.simplified_classified, &self.traditional_classified] {
+      writer.write_len(map.len());
 
-          for _ in 0..found_word.chars().count() - 1 {
-            chars.next();
-          }
+      for (classifier, entries) in map {
+        writer.write_str(classifier);
+        write_entries(&mut writer, entries);
+      }
+    }
 
-          continue;
+    for map in [&self.simplified_conversions, &self.traditional_conversions] {
+      writer.write_len(map.len());
+
+      for (&ch, conversions) in map {
+        writer.write_char(ch);
+        writer.write_len(conversions.len());
+
+        for conversion in conversions {
+          writer.write_char(conversion.target);
+          writer.write_len(conversion.whole_words);
+          writer.write_len(conversion.words.len());
+
+          for word in &conversion.words {
+            writer.write_str(word);
+          }
         }
       }
+    }
 
-      // Match exactly one Chinese character
-
-      let is_chinese = |ch: &char| {
-        !ch.is_ascii_alphanumeric()
-          && (CHINESE_PUNCTUATION.contains(&ch)
-            || self
-              .get(&ch.to_string(), DictionaryType::Simplified)
-              .map(|vec| vec.len() > 0)
-              .unwrap_or(false)
-            || self
-              .get(&ch.to_string(), DictionaryType::Traditional)
-              .map(|vec| vec.len() > 0)
-              .unwrap_or(false))
-      };
+    for syllables in [
+      self.pinyin_syllables.keys().collect::<Vec<_>>(),
+      self.jyutping_syllables.keys().collect(),
+    ] {
+      writer.write_len(syllables.len());
 
-      if ch.is_ascii_whitespace() || is_chinese(&ch) {
-        push_token(&ch.to_string());
-        continue;
+      for syllable in syllables {
+        writer.write_str(syllable);
       }
+    }
 
-      // Handle non-Chinese characters
+    writer.write_len(self.sources.len());
 
-      let mut word = String::new();
+    for source in &self.sources {
+      writer.write_str(&source.name);
+      writer.write_u32(source.priority as u32);
+      writer.write_u8(match source.mode {
+        OverlayMode::Shadow => 1,
+        OverlayMode::Append => 2,
+      });
+    }
 
-      word.push(ch);
+    writer.finish(SnapshotKind::Word)
+  }
 
-      while let Some((_, next_ch)) = chars.peek() {
-        if next_ch.is_ascii_whitespace() || is_chinese(next_ch) {
-          break;
-        }
+  pub fn from_snapshot(data: &[u8]) -> Result<Self, SnapshotError> {
+    let mut reader = SnapshotReader::new(data, SnapshotKind::Word)?;
+    let len = reader.read_len()?;
+    let table =
+      reader.read_u32_array(len.saturating_mul(SNAPSHOT_ENTRY_LEN))?;
+    let entries = (0..len)
+      .map(|i| {
+        let field = |j| table.get(i * SNAPSHOT_ENTRY_LEN + j);
+
+        Ok(WordEntry {
+          traditional: reader.string(field(0))?,
+          simplified: reader.string(field(1))?,
+          pinyin: reader.string(field(2))?,
+          jyutping: match field(3) {
+            SNAPSHOT_NONE => None,
+            id => Some(reader.string(id)?),
+          },
+          english: reader.string(field(4))?,
+          source: reader.string(field(5))?,
+        })
+      })
+      .collect::<Result<Vec<_>, SnapshotError>>()?;
+    let mut read_entries = |reader: &mut SnapshotReader| {
+      (0..reader.read_len()?)
+        .map(|_| {
+          entries
+            .get(reader.read_len()?)
+            .cloned()
+            .ok_or(SnapshotError::InvalidData)
+        })
+        .collect::<Result<Vec<_>, _>>()
+    };
+
+    let simplified = Trie::read_snapshot(&mut reader, &mut read_entries)?;
+    let traditional = Trie::read_snapshot(&mut reader, &mut read_entries)?;
+    let pinyin = Trie::read_snapshot(&mut reader, &mut read_entries)?;
+    let jyutping = Trie::read_snapshot(&mut reader, &mut read_entries)?;
+    let mut classified = [HashMap::default(), HashMap::default()];
 
-        word.push(*next_ch);
-        chars.next();
+    for map in classified.iter_mut() {
+      for _ in 0..reader.read_len()? {
+        map.insert(reader.read_str()?, read_entries(&mut reader)?);
       }
+    }
+
+    let mut conversions = [HashMap::default(), HashMap::default()];
+
+    for map in conversions.iter_mut() {
+      for _ in 0..reader.read_len()? {
+        let ch = reader.read_char()?;
+        let vec = (0..reader.read_len()?)
+          .map(|_| {
+            Ok(CharConversion {
+              target: reader.read_char()?,
+              whole_words: reader.read_len()?,
+              words: (0..reader.read_len()?)
+                .map(|_| reader.read_str())
+                .collect::<Result<_, _>>()?,
+            })
+          })
+          .collect::<Result<_, SnapshotError>>()?;
+
+        map.insert(ch, vec);
+      }
+    }
+
+    let mut pinyin_syllables = HashMap::default();
+    let mut jyutping_syllables = HashMap::default();
 
-      push_token(&word);
+    for _ in 0..reader.read_len()? {
+      let syllable = reader.read_str_ref()?;
+      let parsed =
+        Syllable::parse(syllable).ok_or(SnapshotError::InvalidData)?;
+
+      pinyin_syllables.insert(syllable.to_string(), parsed);
+    }
+
+    for _ in 0..reader.read_len()? {
+      let syllable = reader.read_str_ref()?;
+      let parsed =
+        JyutpingSyllable::parse(syllable).ok_or(SnapshotError::InvalidData)?;
+
+      jyutping_syllables.insert(syllable.to_string(), parsed);
     }
 
-    tokens
+    let sources = (0..reader.read_len()?)
+      .map(|_| {
+        Ok(DictionarySource {
+          name: reader.read_str()?,
+          priority: reader.read_u32()? as i32,
+          mode: match reader.read_u8()? {
+            1 => OverlayMode::Shadow,
+            2 => OverlayMode::Append,
+            _ => return Err(SnapshotError::InvalidData),
+          },
+        })
+      })
+      .collect::<Result<_, _>>()?;
+    let [simplified_classified, traditional_classified] = classified;
+    let [simplified_conversions, traditional_conversions] = conversions;
+
+    reader.finish()?;
+    Ok(Self {
+      simplified,
+      traditional,
+      pinyin,
+      jyutping,
+      simplified_classified,
+      traditional_classified,
+      simplified_conversions,
+      traditional_conversions,
+      pinyin_syllables,
+      jyutping_syllables,
+      sources,
+    })
   }
-}
 
-#[cfg(test)]
-mod tests {
-  use once_cell::sync::Lazy;
+  /// Adds `entry` to the classifier index for each classifier listed in its
+  /// `CL:` senses.
+  fn insert_classified(&mut self, entry: &WordEntry) {
+    for sense in parse_senses(&entry.english) {
+      let Sense::Classifiers { classifiers } = sense else {
+        continue;
+      };
 
-  use super::{DictionaryType, WordDictionary};
+      for link in classifiers {
+        for (map, classifier) in [
+          (&mut self.simplified_classified, link.simplified),
+          (&mut self.traditional_classified, link.traditional),
+        ] {
+          let entries = map.entry(classifier.into()).or_default();
 
-  static CEDICT_DATA: Lazy<WordDictionary> = Lazy::new(|| {
-    WordDictionary::new(include_str!(
-      "../../../data/cedict_1_0_ts_utf-8_mdbg.txt"
-    ))
-  });
+          if !entries
+            .last()
+            .is_some_and(|last| last.is_same_reading(entry))
+          {
+            entries.push(entry.clone());
+          }
+        }
+      }
+    }
+  }
 
-  #[test]
-  fn can_get_word_entry() {
-    let data = CEDICT_DATA.get("识字", DictionaryType::Simplified).unwrap();
+  fn remove_classified(&mut self, entry: &WordEntry) {
+    for sense in parse_senses(&entry.english) {
+      let Sense::Classifiers { classifiers } = sense else {
+        continue;
+      };
 
-    assert_eq!(data.len(), 1);
-    assert_eq!(&*data[0].simplified, "识字");
-    assert_eq!(&*data[0].traditional, "識字");
+      for link in classifiers {
+        for (map, classifier) in [
+          (&mut self.simplified_classified, link.simplified),
+          (&mut self.traditional_classified, link.traditional),
+        ] {
+          if let Some(entries) = map.get_mut(classifier) {
+            entries.retain(|other| !other.is_same_reading(entry));
+
+            if entries.is_empty() {
+              map.remove(classifier);
+            }
+          }
+        }
+      }
+    }
   }
 
-  #[test]
-  fn can_get_all_words_with_prefix() {
-    let data = CEDICT_DATA
-      .iter_prefix("中国", DictionaryType::Simplified)
-      .collect::<Vec<_>>();
+  /// Returns the pairs of aligned characters of both forms of `entry` for
+  /// the conversion indices, simplified first. Entries whose forms differ in
+  /// length have none.
+  fn conversion_pairs(
+    entry: &WordEntry,
+  ) -> impl Iterator<Item = (char, char)> + '_ {
+    let aligned =
+      entry.simplified.chars().count() == entry.traditional.chars().count();
 
-    assert!(data.len() > 2);
+    entry
+      .simplified
+      .chars()
+      .zip(entry.traditional.chars())
+      .filter(move |_| aligned)
   }
 
-  #[test]
-  fn can_get_multiple_word_entries() {
-    let data = CEDICT_DATA.get("沈", DictionaryType::Simplified).unwrap();
+  /// Adds `entry` to the conversion indices for each of its characters.
+  fn insert_conversions(&mut self, entry: &WordEntry) {
+    let whole_word = entry.simplified.chars().count() == 1;
 
-    assert_eq!(data.len(), 4);
+    for (simplified, traditional) in Self::conversion_pairs(entry) {
+      for (map, from, to) in [
+        (&mut self.simplified_conversions, simplified, traditional),
+        (&mut self.traditional_conversions, traditional, simplified),
+      ] {
+        let conversions = map.entry(from).or_default();
+        let index = match conversions
+          .iter()
+          .position(|conversion| conversion.target == to)
+        {
+          Some(index) => index,
+          None => {
+            conversions.push(CharConversion {
+              target: to,
+              whole_words: 0,
+              words: vec![],
+            });
+            conversions.len() - 1
+          }
+        };
+        let conversion = &mut conversions[index];
+
+        conversion.whole_words += whole_word as usize;
+        conversion.words.push(entry.simplified.clone());
+      }
+    }
   }
 
-  #[test]
-  fn should_tokenize_simple_sentence() {
-    let tokens = CEDICT_DATA.tokenize("我是中国人。");
+  fn remove_conversions(&mut self, entry: &WordEntry) {
+    let whole_word = entry.simplified.chars().count() == 1;
 
-    assert_eq!(
-      tokens.iter().map(|token| &*token.value).collect::<Vec<_>>(),
-      vec!["我", "是", "中国人", "。"]
+    for (simplified, traditional) in Self::conversion_pairs(entry) {
+      for (map, from, to) in [
+        (&mut self.simplified_conversions, simplified, traditional),
+        (&mut self.traditional_conversions, traditional, simplified),
+      ] {
+        let Some(conversions) = map.get_mut(&from) else {
+          continue;
+        };
+
+        if let Some(conversion) = conversions
+          .iter_mut()
+          .find(|conversion| conversion.target == to)
+        {
+          if let Some(index) = conversion
+            .words
+            .iter()
+            .position(|word| *word == entry.simplified)
+          {
+            conversion.words.remove(index);
+            conversion.whole_words -= whole_word as usize;
+          }
+        }
+
+        conversions.retain(|conversion| !conversion.words.is_empty());
+
+        if conversions.is_empty() {
+          map.remove(&from);
+        }
+      }
+    }
+  }
+
+  fn insert_into<K: KeyElement, Q: TrieKey<K> + ?Sized>(
+    trie: &mut Trie<Vec<WordEntry>, K>,
+    word: &Q,
+    entry: WordEntry,
+  ) -> Option<WordEntry> {
+    let entries = trie.entry(word).or_insert_with(|| Vec::with_capacity(1))?;
+
+    match entries
+      .iter_mut()
+      .find(|other| other.is_same_reading(&entry))
+    {
+      Some(other) => Some(mem::replace(other, entry)),
+      None => {
+        entries.push(entry);
+        None
+      }
+    }
+  }
+
+  fn remove_from<K: KeyElement, Q: TrieKey<K> + ?Sized>(
+    trie: &mut Trie<Vec<WordEntry>, K>,
+    word: &Q,
+    entry: &WordEntry,
+  ) -> Option<WordEntry> {
+    let entries = trie.get_mut(word)?;
+    let index = entries
+      .iter()
+      .position(|other| other.is_same_reading(entry))?;
+    let result = entries.remove(index);
+
+    if entries.is_empty() {
+      trie.remove(word);
+    }
+
+    Some(result)
+  }
+
+  /// Adds `entry` to the dictionary. An existing entry with the same
+  /// traditional, simplified and pinyin forms gets replaced and returned.
+  pub fn insert_entry(&mut self, entry: WordEntry) -> Option<WordEntry> {
+    let simplified = entry.simplified.clone();
+    let traditional = entry.traditional.clone();
+    let pinyin = Self::pinyin_key(&entry.pinyin);
+
+    Self::insert_syllables(
+      &mut self.pinyin_syllables,
+      &pinyin,
+      Syllable::parse,
+    );
+    Self::insert_into(&mut self.simplified, &*simplified, entry.clone());
+    Self::insert_into(&mut self.pinyin, &pinyin, entry.clone());
+
+    let result =
+      Self::insert_into(&mut self.traditional, &*traditional, entry.clone());
+
+    if let Some(old) = &result {
+      self.remove_classified(old);
+      self.remove_jyutping(old);
+      self.remove_conversions(old);
+    }
+
+    self.insert_classified(&entry);
+    self.insert_jyutping(&entry);
+    self.insert_conversions(&entry);
+    result
+  }
+
+  /// Removes the entry with the same traditional, simplified and pinyin forms
+  /// as `entry`.
+  pub fn remove_entry(&mut self, entry: &WordEntry) -> Option<WordEntry> {
+    Self::remove_from(&mut self.simplified, &*entry.simplified, entry);
+    Self::remove_from(
+      &mut self.pinyin,
+      &Self::pinyin_key(&entry.pinyin),
+      entry,
+    );
+    let result =
+      Self::remove_from(&mut self.traditional, &*entry.traditional, entry);
+
+    if let Some(old) = &result {
+      self.remove_classified(old);
+      self.remove_jyutping(old);
+      self.remove_conversions(old);
+    }
+
+    result
+  }
+
+  /// Removes all entries of `word` and returns them.
+  pub fn remove_word(
+    &mut self,
+    word: &str,
+    ty: DictionaryType,
+  ) -> Vec<WordEntry> {
+    let (trie, other_trie) = match ty {
+      DictionaryType::Simplified => {
+        (&mut self.simplified, &mut self.traditional)
+      }
+      DictionaryType::Traditional => {
+        (&mut self.traditional, &mut self.simplified)
+      }
+    };
+    let entries = trie.remove(word).unwrap_or_default();
+
+    for entry in entries.iter() {
+      let other_word = match ty {
+        DictionaryType::Simplified => &entry.traditional,
+        DictionaryType::Traditional => &entry.simplified,
+      };
+
+      Self::remove_from(other_trie, &**other_word, entry);
+      Self::remove_from(
+        &mut self.pinyin,
+        &Self::pinyin_key(&entry.pinyin),
+        entry,
+      );
+    }
+
+    for entry in entries.iter() {
+      self.remove_classified(entry);
+      self.remove_jyutping(entry);
+      self.remove_conversions(entry);
+    }
+
+    entries
+  }
+
+  /// Overlays the CEDICT formatted `data` as `source` on all entries added
+  /// so far. Which entries of a word remain depends on the priorities and
+  /// [`OverlayMode`]s of the sources involved. Entries losing against
+  /// existing entries are reported as skipped.
+  pub fn add_source(
+    &mut self,
+    data: &str,
+    source: DictionarySource,
+  ) -> ParseReport {
+    let mut report = ParseReport::default();
+
+    self.sources.push(source.clone());
+
+    for (i, line) in data.lines().enumerate() {
+      let line = line.trim();
+
+      if line.is_empty() || line.starts_with("#") {
+        continue;
+      }
+
+      let result = Self::parse_line(line, &source.name)
+        .map(|entry| entry.filter(|entry| !self.is_overridden(entry, &source)));
+      let Some(entry) = report.record(i, result) else {
+        continue;
+      };
+
+      if source.mode == OverlayMode::Shadow {
+        let shadowed = self
+          .iter_headword(&entry)
+          .filter(|other| {
+            other.source != source.name
+              && self.source_rank(other).0 <= source.priority
+          })
+          .cloned()
+          .collect::<Vec<_>>();
+
+        for other in shadowed {
+          self.remove_entry(&other);
+        }
+      }
+
+      self.insert_entry(entry);
+    }
+
+    report
+  }
+
+  /// Merges the Jyutping of a readings file such as the CC-Canto readings
+  /// for CC-CEDICT, with lines of the form
+  /// `傳統 传统 [chuan2 tong3] {cyun4 tung2}`, into the entries with the same
+  /// traditional, simplified and pinyin forms. Lines matching no entry are
+  /// reported as skipped.
+  pub fn merge_jyutping(&mut self, data: &str) -> ParseReport {
+    let mut report = ParseReport::default();
+
+    for (i, line) in data.lines().enumerate() {
+      let line = line.trim();
+
+      if line.is_empty() || line.starts_with("#") {
+        continue;
+      }
+
+      let result = Self::parse_fields(line).and_then(|fields| {
+        let jyutping: Arc<str> = fields
+          .jyutping
+          .ok_or(ParseErrorKind::MissingField("jyutping"))?
+          .into();
+        let entries = self
+          .get(fields.traditional, DictionaryType::Traditional)
+          .into_iter()
+          .flatten()
+          .filter(|entry| {
+            &*entry.simplified == fields.simplified
+              && *entry.pinyin == *fields.pinyin
+          })
+          .map(|entry| WordEntry {
+            jyutping: Some(jyutping.clone()),
+            ..entry.clone()
+          })
+          .collect::<Vec<_>>();
+
+        Ok((!entries.is_empty()).then_some(entries))
+      });
+      let Some(entries) = report.record(i, result) else {
+        continue;
+      };
+
+      for entry in entries {
+        self.insert_entry(entry);
+      }
+    }
+
+    report
+  }
+
+  pub fn sources(&self) -> &[DictionarySource] {
+    &self.sources
+  }
+
+  /// Returns the source added under `name`, if any.
+  pub fn source(&self, name: &str) -> Option<&DictionarySource> {
+    self.sources.iter().find(|source| &*source.name == name)
+  }
+
+  /// Returns the priority and mode of the source of `entry`. Entries of
+  /// unknown sources rank like the base dictionary.
+  fn source_rank(&self, entry: &WordEntry) -> (i32, OverlayMode) {
+    self
+      .sources
+      .iter()
+      .rev()
+      .find(|source| source.name == entry.source)
+      .map(|source| (source.priority, source.mode))
+      .unwrap_or((0, OverlayMode::Append))
+  }
+
+  /// Yields all entries with the same traditional and simplified forms as
+  /// `entry`, including `entry` itself.
+  fn iter_headword<'a>(
+    &'a self,
+    entry: &'a WordEntry,
+  ) -> impl Iterator<Item = &'a WordEntry> {
+    self
+      .get(&entry.traditional, DictionaryType::Traditional)
+      .into_iter()
+      .flatten()
+      .filter(|other| other.simplified == entry.simplified)
+  }
+
+  /// Returns whether an entry of a source with higher priority than `source`
+  /// shadows `entry` or has the same reading.
+  fn is_overridden(
+    &self,
+    entry: &WordEntry,
+    source: &DictionarySource,
+  ) -> bool {
+    self.iter_headword(entry).any(|other| {
+      let (priority, mode) = self.source_rank(other);
+
+      priority > source.priority
+        && (mode == OverlayMode::Shadow || other.is_same_reading(entry))
+    })
+  }
+
+  /// Looks up entries by their exact pinyin, e.g. `zhong1 guo2`, ignoring
+  /// case.
+  pub fn get_by_pinyin(&self, pinyin: &str) -> Option<&Vec<WordEntry>> {
+    self.pinyin.get(&Self::pinyin_key(pinyin))
+  }
+
+  /// Yields all entries whose pinyin starts with the syllables of `pinyin`,
+  /// ordered by pinyin.
+  pub fn iter_pinyin_prefix(
+    &self,
+    pinyin: &str,
+  ) -> impl Iterator<Item = &WordEntry> {
+    self
+      .pinyin
+      .iter_prefix(&Self::pinyin_key(pinyin))
+      .flat_map(|(_, vec)| vec.iter())
+  }
+
+  /// Searches entries by pinyin input such as `zhongguo`, `zhong1guo2`,
+  /// `zhong guo` or abbreviated by initials as `zg`. Syllables are split in
+  /// all possible ways, e.g. `xian` finds both `xian` and `xi'an`, and tones
+  /// are optional. Results are ordered by descending frequency.
+  pub fn search_pinyin<'a>(
+    &'a self,
+    query: &str,
+    frequency_dict: &FrequencyDictionary,
+  ) -> Vec<&'a WordEntry> {
+    let query = query
+      .trim()
+      .to_lowercase()
+      .replace("u:", "ü")
+      .replace('v', "ü");
+
+    Self::search_syllables(
+      &self.pinyin,
+      &query,
+      &["zh", "ch", "sh"],
+      frequency_dict,
+    )
+  }
+
+  /// Looks up entries by their exact Jyutping, e.g. `zung1 gwok3`, ignoring
+  /// case.
+  pub fn get_by_jyutping(&self, jyutping: &str) -> Option<&Vec<WordEntry>> {
+    self.jyutping.get(&Self::jyutping_key(jyutping))
+  }
+
+  /// Yields all entries whose Jyutping starts with the syllables of
+  /// `jyutping`, ordered by Jyutping.
+  pub fn iter_jyutping_prefix(
+    &self,
+    jyutping: &str,
+  ) -> impl Iterator<Item = &WordEntry> {
+    self
+      .jyutping
+      .iter_prefix(&Self::jyutping_key(jyutping))
+      .flat_map(|(_, vec)| vec.iter())
+  }
+
+  /// Searches entries by Jyutping input like [`WordDictionary::search_pinyin`]
+  /// does by pinyin, e.g. `zunggwok`, `zung1 gwok3` or `zg`.
+  pub fn search_jyutping<'a>(
+    &'a self,
+    query: &str,
+    frequency_dict: &FrequencyDictionary,
+  ) -> Vec<&'a WordEntry> {
+    Self::search_syllables(
+      &self.jyutping,
+      &query.trim().to_lowercase(),
+      &["ng", "gw", "kw"],
+      frequency_dict,
+    )
+  }
+
+  /// Searches a syllable-keyed index for the normalized `query`. `digraphs`
+  /// are the initials consisting of two letters, which may abbreviate a
+  /// syllable just like its first letter.
+  fn search_syllables<'a>(
+    trie: &'a Trie<Vec<WordEntry>, String>,
+    query: &str,
+    digraphs: &[&str],
+    frequency_dict: &FrequencyDictionary,
+  ) -> Vec<&'a WordEntry> {
+    struct Search<'a, 'b> {
+      query: &'b str,
+      digraphs: &'b [&'b str],
+      visited: HashSet<(*const Trie<Vec<WordEntry>, String>, usize)>,
+      result: Vec<&'a WordEntry>,
+    }
+
+    impl<'a, 'b> Search<'a, 'b> {
+      fn visit(
+        &mut self,
+        trie: &'a Trie<Vec<WordEntry>, String>,
+        value: Option<&'a Vec<WordEntry>>,
+        index: usize,
+      ) {
+        if !self.visited.insert((trie as *const _, index)) {
+          return;
+        }
+
+        let rest = self.query[index..].trim_start_matches([' ', '\'', '’']);
+        let index = self.query.len() - rest.len();
+
+        if rest.is_empty() {
+          self.result.extend(value.into_iter().flatten());
+          return;
+        }
+
+        for (key, value, children) in trie.children() {
+          let (spelling, tone) = match key.char_indices().next_back() {
+            Some((i, tone)) if tone.is_ascii_digit() => (&key[..i], Some(tone)),
+            _ => (key.as_str(), None),
+          };
+
+          if !spelling.starts_with(|ch: char| ch.is_alphabetic()) {
+            // Skip punctuation such as `,` in idioms
+
+            self.visit(children, value, index);
+            continue;
+          }
+
+          if let Some(after) = rest.strip_prefix(spelling) {
+            match after.chars().next() {
+              Some(ch) if ch.is_ascii_digit() => {
+                if Some(ch) == tone {
+                  self.visit(children, value, index + spelling.len() + 1);
+                }
+              }
+              _ => self.visit(children, value, index + spelling.len()),
+            }
+          }
+
+          if tone.is_some() {
+            // Abbreviations by the initial, e.g. `zh` or `z` for `zhong`
+
+            let abbreviations = self
+              .digraphs
+              .iter()
+              .copied()
+              .filter(|initial| spelling.starts_with(initial))
+              .chain(
+                spelling
+                  .get(..1)
+                  .filter(|initial| !"aeo".contains(*initial)),
+              );
+
+            for abbreviation in abbreviations {
+              if abbreviation != spelling && rest.starts_with(abbreviation) {
+                self.visit(children, value, index + abbreviation.len());
+              }
+            }
+          }
+        }
+      }
+    }
+
+    if query.is_empty() {
+      return vec![];
+    }
+
+    let mut search = Search {
+      query,
+      digraphs,
+      visited: HashSet::default(),
+      result: vec![],
+    };
+
+    search.visit(trie, None, 0);
+
+    let mut result = search.result;
+
+    result.sort_by_cached_key(|entry| {
+      Reverse(frequency_dict.get(&entry.simplified).unwrap_or(0))
+    });
+    result
+  }
+
+  pub fn get(&self, word: &str, ty: DictionaryType) -> Option<&Vec<WordEntry>> {
+    match ty {
+      DictionaryType::Simplified => &self.simplified,
+      DictionaryType::Traditional => &self.traditional,
+    }
+    .get(word)
+  }
+
+  /// Parses the definition of `entry` into senses, resolving links to other
+  /// words in this dictionary.
+  pub fn get_senses<'a>(&'a self, entry: &'a WordEntry) -> Vec<Sense<'a>> {
+    let mut senses = parse_senses(&entry.english);
+
+    for link in senses.iter_mut().flat_map(|sense| sense.links_mut()) {
+      link.resolve(self);
+    }
+
+    senses
+  }
+
+  /// Returns the classifiers listed for `word`, resolved to their entries
+  /// where possible.
+  pub fn get_classifiers<'a>(
+    &'a self,
+    word: &str,
+    ty: DictionaryType,
+  ) -> Vec<WordLink<'a>> {
+    let mut result = Vec::<WordLink>::new();
+
+    for entry in self.get(word, ty).into_iter().flatten() {
+      for sense in self.get_senses(entry) {
+        let Sense::Classifiers { classifiers } = sense else {
+          continue;
+        };
+
+        for link in classifiers {
+          if !result.iter().any(|other| {
+            other.traditional == link.traditional
+              && other.simplified == link.simplified
+              && other.pinyin == link.pinyin
+          }) {
+            result.push(link);
+          }
+        }
+      }
+    }
+
+    result
+  }
+
+  /// Returns all entries listing `classifier` as a classifier, most frequent
+  /// words first.
+  pub fn get_classified_words(
+    &self,
+    classifier: &str,
+    ty: DictionaryType,
+    frequency_dict: &FrequencyDictionary,
+  ) -> Vec<&WordEntry> {
+    let mut result = match ty {
+      DictionaryType::Simplified => &self.simplified_classified,
+      DictionaryType::Traditional => &self.traditional_classified,
+    }
+    .get(classifier)
+    .map(|entries| entries.iter().collect::<Vec<_>>())
+    .unwrap_or_default();
+
+    result.sort_by_cached_key(|entry| {
+      Reverse(frequency_dict.get(&entry.simplified).unwrap_or(0))
+    });
+
+    result
+  }
+
+  pub fn iter_prefix(
+    &self,
+    word: &str,
+    ty: DictionaryType,
+  ) -> impl Iterator<Item = &WordEntry> {
+    match ty {
+      DictionaryType::Simplified => &self.simplified,
+      DictionaryType::Traditional => &self.traditional,
+    }
+    .iter_prefix(word)
+    .flat_map(|(_, vec)| vec.iter())
+  }
+
+  pub fn iter_words_with_prefix<'a>(
+    &'a self,
+    word: &str,
+    max_depth: Option<usize>,
+    ty: DictionaryType,
+  ) -> impl Iterator<Item = (String, &'a Vec<WordEntry>)> {
+    match ty {
+      DictionaryType::Simplified => &self.simplified,
+      DictionaryType::Traditional => &self.traditional,
+    }
+    .iter_prefix_with_depth(word, max_depth.unwrap_or(usize::MAX))
+  }
+
+  pub fn iter_common_prefixes<'a, 'b>(
+    &'a self,
+    text: &'b str,
+    ty: DictionaryType,
+  ) -> impl Iterator<Item = (&'b str, &'a Vec<WordEntry>)> {
+    match ty {
+      DictionaryType::Simplified => &self.simplified,
+      DictionaryType::Traditional => &self.traditional,
+    }
+    .iter_common_prefixes(text)
+    .map(move |(len, entries)| (&text[..len], entries))
+  }
+
+  pub fn iter(&self) -> impl Iterator<Item = &WordEntry> {
+    self.traditional.iter().flat_map(|(_, vec)| vec.iter())
+  }
+
+  pub fn iter_including_subslice<'a>(
+    &'a self,
+    slice: &'a str,
+    ty: DictionaryType,
+  ) -> impl Iterator<Item = &'a WordEntry> {
+    self.iter().filter(move |entry| {
+      let word = match ty {
+        DictionaryType::Simplified => &entry.simplified,
+        DictionaryType::Traditional => &entry.traditional,
+      };
+
+      &**word != slice && word.contains(slice)
+    })
+  }
+
+  /// Returns "did you mean" suggestions for `word`, i.e. entries of words
+  /// other than `word` within `max_distance` edits, closest first.
+  pub fn get_suggestions(
+    &self,
+    word: &str,
+    max_distance: usize,
+    ty: DictionaryType,
+  ) -> Vec<(&WordEntry, usize)> {
+    match ty {
+      DictionaryType::Simplified => &self.simplified,
+      DictionaryType::Traditional => &self.traditional,
+    }
+    .find_within_distance(word, max_distance)
+    .into_iter()
+    .filter(|&(_, _, distance)| distance > 0)
+    .flat_map(|(_, entries, distance)| {
+      entries.iter().map(move |entry| (entry, distance))
+    })
+    .collect()
+  }
+
+  /// Yields all entries whose word matches `pattern`, ordered by word.
+  /// `has_component` decides whether a character contains a component.
+  pub fn iter_matching<'a>(
+    &'a self,
+    pattern: &WordPattern,
+    ty: DictionaryType,
+    has_component: impl Fn(char, char) -> bool,
+  ) -> impl Iterator<Item = &'a WordEntry> {
+    pattern
+      .find_in(
+        match ty {
+          DictionaryType::Simplified => &self.simplified,
+          DictionaryType::Traditional => &self.traditional,
+        },
+        has_component,
+      )
+      .into_iter()
+      .flat_map(|(_, entries)| entries.iter())
+  }
+
+  /// Finds words which sound like `word` through the pinyin index. Each
+  /// result comes with the closest mode it matches in, up to `mode`.
+  pub fn iter_homophones<'a>(
+    &'a self,
+    word: &'a str,
+    ty: DictionaryType,
+    mode: HomophoneMode,
+  ) -> impl Iterator<Item = (&'a WordEntry, HomophoneMode)> {
+    let readings = self
+      .get(word, ty)
+      .into_iter()
+      .flatten()
+      .map(|entry| Self::pinyin_key(&entry.pinyin))
+      .collect();
+
+    Self::find_homophones(
+      &self.pinyin,
+      readings,
+      word,
+      ty,
+      mode,
+      |a, b| match (self.pinyin_syllables.get(a), self.pinyin_syllables.get(b))
+      {
+        (Some(a), Some(b))
+          if a.initial() == b.initial() && a.final_() == b.final_() =>
+        {
+          Some(HomophoneMode::ToneInsensitive)
+        }
+        (Some(a), Some(b)) if a.is_confusable_with(b) => {
+          Some(HomophoneMode::Confusable)
+        }
+        _ => None,
+      },
+    )
+  }
+
+  /// Finds words which sound like `word` in Cantonese through the Jyutping
+  /// index, like [`WordDictionary::iter_homophones`] does for Mandarin.
+  pub fn iter_jyutping_homophones<'a>(
+    &'a self,
+    word: &'a str,
+    ty: DictionaryType,
+    mode: HomophoneMode,
+  ) -> impl Iterator<Item = (&'a WordEntry, HomophoneMode)> {
+    let readings = self
+      .get(word, ty)
+      .into_iter()
+      .flatten()
+      .filter_map(|entry| entry.jyutping.as_deref().map(Self::jyutping_key))
+      .collect();
+
+    Self::find_homophones(&self.jyutping, readings, word, ty, mode, |a, b| {
+      match (
+        self.jyutping_syllables.get(a),
+        self.jyutping_syllables.get(b),
+      ) {
+        (Some(a), Some(b))
+          if a.initial() == b.initial() && a.final_() == b.final_() =>
+        {
+          Some(HomophoneMode::ToneInsensitive)
+        }
+        (Some(a), Some(b)) if a.is_confusable_with(b) => {
+          Some(HomophoneMode::Confusable)
+        }
+        _ => None,
+      }
+    })
+  }
+
+  /// Finds the entries of other words sounding like any of `readings` in a
+  /// syllable-keyed index. `compare` grades two different syllables by their
+  /// parsed forms and returns `None` if they don't sound alike.
+  fn find_homophones<'a>(
+    trie: &'a Trie<Vec<WordEntry>, String>,
+    readings: Vec<Vec<String>>,
+    word: &'a str,
+    ty: DictionaryType,
+    mode: HomophoneMode,
+    compare: impl Fn(&str, &str) -> Option<HomophoneMode>,
+  ) -> impl Iterator<Item = (&'a WordEntry, HomophoneMode)> {
+    fn visit<'a>(
+      trie: &'a Trie<Vec<WordEntry>, String>,
+      value: Option<&'a Vec<WordEntry>>,
+      keys: &[String],
+      matched: HomophoneMode,
+      mode: HomophoneMode,
+      compare: &dyn Fn(&str, &str) -> Option<HomophoneMode>,
+      result: &mut Vec<(&'a WordEntry, HomophoneMode)>,
+    ) {
+      let Some((key, keys)) = keys.split_first() else {
+        result
+          .extend(value.into_iter().flatten().map(|entry| (entry, matched)));
+        return;
+      };
+
+      for (other, value, children) in trie.children() {
+        let level = if other == key {
+          HomophoneMode::Exact
+        } else {
+          match compare(key, other) {
+            Some(level) => level,
+            None => continue,
+          }
+        };
+
+        if level <= mode {
+          visit(
+            children,
+            value,
+            keys,
+            matched.max(level),
+            mode,
+            compare,
+            result,
+          );
+        }
+      }
+    }
+
+    let mut result = vec![];
+
+    for keys in readings {
+      if mode == HomophoneMode::Exact {
+        // Only the very same syllables match, no need to walk the index
+
+        result.extend(
+          trie
+            .get(&keys)
+            .into_iter()
+            .flatten()
+            .map(|entry| (entry, HomophoneMode::Exact)),
+        );
+      } else {
+        visit(
+          trie,
+          None,
+          &keys,
+          HomophoneMode::Exact,
+          mode,
+          &compare,
+          &mut result,
+        );
+      }
+    }
+
+    // Entries reached through several readings keep their closest match
+
+    let mut seen = HashSet::default();
+
+    result.sort_by_key(|&(_, mode)| mode);
+    result.retain(|&(entry, _)| seen.insert(entry as *const WordEntry));
+
+    result.into_iter().filter(move |(entry, _)| {
+      word
+        != match ty {
+          DictionaryType::Simplified => &*entry.simplified,
+          DictionaryType::Traditional => &*entry.traditional,
+        }
+        && !is_variant_entry(entry)
+    })
+  }
+
+  /// Returns all entries whose reading matches `query`, most frequent words
+  /// first.
+  pub fn query_pinyin(
+    &self,
+    query: &PinyinQuery,
+    frequency_dict: &FrequencyDictionary,
+  ) -> Vec<&WordEntry> {
+    let mut result = query
+      .find_in(&self.pinyin)
+      .into_iter()
+      .flatten()
+      .filter(|entry| !is_variant_entry(entry))
+      .collect::<Vec<_>>();
+
+    result.sort_by_cached_key(|entry| {
+      Reverse(frequency_dict.get(&entry.simplified).unwrap_or(0))
+    });
+
+    result
+  }
+
+  fn has_entries(&self, word: &str) -> bool {
+    self
+      .get(word, DictionaryType::Simplified)
+      .map(|vec| !vec.is_empty())
+      .unwrap_or(false)
+      || self
+        .get(word, DictionaryType::Traditional)
+        .map(|vec| !vec.is_empty())
+        .unwrap_or(false)
+  }
+
+  /// Returns the longest word of two or more characters at the start of
+  /// `input`.
+  fn longest_word<'b>(&self, input: &'b str) -> Option<&'b str> {
+    self
+      .iter_common_prefixes(input, DictionaryType::Simplified)
+      .chain(self.iter_common_prefixes(input, DictionaryType::Traditional))
+      .map(|(word, _)| word)
+      .filter(|word| word.chars().nth(1).is_some())
+      .max_by_key(|word| word.len())
+  }
+
+  /// Returns the token at the start of `input` if no word of two or more
+  /// characters matches, i.e. a single Chinese character, punctuation mark or
+  /// whitespace, or a run of non-Chinese characters of the same kind.
+  fn next_unit<'b>(&self, input: &'b str) -> &'b str {
+    let mut chars = input.char_indices().peekable();
+    let is_single = |ch: char, kind| {
+      matches!(
+        kind,
+        TokenKind::UnknownHanzi
+          | TokenKind::Punctuation
+          | TokenKind::Whitespace
+      ) || self.has_entries(ch.encode_utf8(&mut [0; 4]))
+    };
+
+    match chars.next() {
+      Some((_, ch)) if is_single(ch, TokenKind::of_char(ch)) => {
+        &input[..ch.len_utf8()]
+      }
+      Some((_, ch)) => {
+        let run_kind = TokenKind::of_char(ch);
+        let mut end = input.len();
+
+        while let Some((i, ch)) = chars.next() {
+          let kind = TokenKind::of_char(ch);
+
+          // Keep decimal and thousands separators within numbers
+
+          let is_separator = run_kind == TokenKind::Number
+            && matches!(ch, '.' | ',' | '．')
+            && chars
+              .peek()
+              .map(|&(_, next)| TokenKind::of_char(next) == TokenKind::Number)
+              .unwrap_or(false);
+
+          if !is_separator && (kind != run_kind || is_single(ch, kind)) {
+            end = i;
+            break;
+          }
+        }
+
+        &input[..end]
+      }
+      None => input,
+    }
+  }
+
+  fn make_token(&self, word: &str, start: Position) -> Token {
+    let has_entries = self.has_entries(word);
+    let kind = match word.chars().next().map(TokenKind::of_char) {
+      Some(TokenKind::Punctuation) => TokenKind::Punctuation,
+      Some(TokenKind::Whitespace) => TokenKind::Whitespace,
+      _ if has_entries => TokenKind::ChineseWord,
+      Some(kind) => kind,
+      None => TokenKind::Symbol,
+    };
+
+    let end = start.advance(word);
+
+    Token {
+      value: word.into(),
+      has_entries,
+      kind,
+      byte_span: Span {
+        start: start.byte,
+        end: end.byte,
+      },
+      char_span: Span {
+        start: start.char,
+        end: end.char,
+      },
+      utf16_span: Span {
+        start: start.utf16,
+        end: end.utf16,
+      },
+    }
+  }
+
+  fn make_tokens<'b>(
+    &self,
+    words: impl Iterator<Item = &'b str>,
+  ) -> Vec<Token> {
+    let mut position = Position::default();
+
+    words
+      .map(|word| {
+        let token = self.make_token(word, position);
+
+        position = position.advance(word);
+        token
+      })
+      .collect()
+  }
+
+  pub fn tokenize(&self, input: &str) -> Vec<Token> {
+    self.tokenize_with_mode(input, TokenizeMode::Greedy)
+  }
+
+  pub fn tokenize_with_mode(
+    &self,
+    input: &str,
+    mode: TokenizeMode,
+  ) -> Vec<Token> {
+    match mode {
+      TokenizeMode::Greedy => {
+        let mut words = vec![];
+        let mut i = 0;
+
+        while i < input.len() {
+          // First, try to match two or more characters, otherwise match
+          // exactly one Chinese character or non-Chinese characters
+
+          let word = self
+            .longest_word(&input[i..])
+            .unwrap_or_else(|| self.next_unit(&input[i..]));
+
+          words.push(word);
+          i += word.len();
+        }
+
+        self.make_tokens(words.into_iter())
+      }
+      TokenizeMode::Statistical(frequency_dict) => {
+        let (words, _) = self
+          .segment(input, frequency_dict, 1)
+          .pop()
+          .unwrap_or_default();
+
+        self.make_tokens(words.into_iter())
+      }
+    }
+  }
+
+  /// Converts `input` into the script `to` word by word, segmenting it
+  /// statistically. Words with several conversions take the one whose
+  /// characters are words on their own, then the one whose characters are
+  /// used by the most frequent words, and are reported as ambiguities. Words
+  /// without entries are kept as is.
+  pub fn convert(
+    &self,
+    input: &str,
+    to: DictionaryType,
+    frequency_dict: &FrequencyDictionary,
+  ) -> Conversion {
+    let from = match to {
+      DictionaryType::Simplified => DictionaryType::Traditional,
+      DictionaryType::Traditional => DictionaryType::Simplified,
+    };
+    let mut cache = HashMap::<Arc<str>, Vec<Arc<str>>>::default();
+    let mut text = String::with_capacity(input.len());
+    let mut ambiguities = vec![];
+    let mut position = Position::default();
+
+    for token in
+      self.tokenize_with_mode(input, TokenizeMode::Statistical(frequency_dict))
+    {
+      let candidates = cache
+        .entry(token.value.clone())
+        .or_insert_with(|| {
+          self.conversion_candidates(&token.value, from, to, frequency_dict)
+        })
+        .clone();
+      let word = candidates.first().unwrap_or(&token.value).clone();
+
+      if candidates.len() > 1 {
+        ambiguities.push(Ambiguity {
+          source: token,
+          target: self.make_token(&word, position),
+          candidates,
+        });
+      }
+
+      text.push_str(&word);
+      position = position.advance(&word);
+    }
+
+    Conversion { text, ambiguities }
+  }
+
+  /// Returns the distinct forms of `word` in the script `to`, most used
+  /// first.
+  fn conversion_candidates(
+    &self,
+    word: &str,
+    from: DictionaryType,
+    to: DictionaryType,
+    frequency_dict: &FrequencyDictionary,
+  ) -> Vec<Arc<str>> {
+    let form = |entry: &WordEntry, ty| match ty {
+      DictionaryType::Simplified => entry.simplified.clone(),
+      DictionaryType::Traditional => entry.traditional.clone(),
+    };
+    let mut candidates = Vec::<Arc<str>>::new();
+
+    for entry in self.get(word, from).into_iter().flatten() {
+      let candidate = form(entry, to);
+
+      if !candidates.contains(&candidate) {
+        candidates.push(candidate);
+      }
+    }
+
+    if candidates.len() > 1 {
+      // Prefer characters that are words on their own, then those used by
+      // the most frequent words
+
+      let conversions = match from {
+        DictionaryType::Simplified => &self.simplified_conversions,
+        DictionaryType::Traditional => &self.traditional_conversions,
+      };
+      let rank = |candidate: &str| {
+        word.chars().zip(candidate.chars()).fold(
+          (0, 0.0),
+          |(whole_words, weight), (from, to)| match conversions
+            .get(&from)
+            .and_then(|conversions| {
+              conversions
+                .iter()
+                .find(|conversion| conversion.target == to)
+            }) {
+            Some(conversion) => (
+              whole_words + (conversion.whole_words > 0) as usize,
+              weight + conversion.weight(frequency_dict),
+            ),
+            None => (whole_words, weight),
+          },
+        )
+      };
+      let mut ranked = candidates
+        .into_iter()
+        .map(|candidate| (rank(&candidate), candidate))
+        .collect::<Vec<_>>();
+
+      ranked.sort_by(|((x_whole, x), _), ((y_whole, y), _)| {
+        y_whole.cmp(x_whole).then(y.total_cmp(x))
+      });
+      candidates = ranked.into_iter().map(|(_, word)| word).collect();
+    }
+
+    candidates
+  }
+
+  /// Returns the character offsets of `input` as byte indices, including the
+  /// end of `input`, together with the words starting at each offset that are
+  /// reachable from the start.
+  fn word_graph<'b>(&self, input: &'b str) -> (Vec<usize>, Vec<Vec<&'b str>>) {
+    let offsets = input
+      .char_indices()
+      .map(|(i, _)| i)
+      .chain([input.len()])
+      .collect::<Vec<_>>();
+    let mut graph = vec![vec![]; offsets.len()];
+    let mut reachable = vec![false; offsets.len()];
+
+    reachable[0] = true;
+
+    for (i, &offset) in offsets.iter().enumerate() {
+      if !reachable[i] || offset == input.len() {
+        continue;
+      }
+
+      let sliced_input = &input[offset..];
+      let words: &mut Vec<&str> = &mut graph[i];
+
+      for word in self
+        .iter_common_prefixes(sliced_input, DictionaryType::Simplified)
+        .chain(
+          self.iter_common_prefixes(sliced_input, DictionaryType::Traditional),
+        )
+        .map(|(word, _)| word)
+        .chain([self.next_unit(sliced_input)])
+      {
+        if !words.contains(&word) {
+          words.push(word);
+        }
+      }
+
+      words.sort_by_key(|word| word.len());
+
+      for word in words.iter() {
+        if let Ok(j) = offsets.binary_search(&(offset + word.len())) {
+          reachable[j] = true;
+        }
+      }
+    }
+
+    (offsets, graph)
+  }
+
+  /// Returns the lattice of all possible words in `input`, i.e. for each
+  /// character offset, the tokens of all words starting there, ordered by
+  /// length. Offsets that no segmentation can reach have no tokens.
+  pub fn lattice(&self, input: &str) -> Vec<Vec<Token>> {
+    let (_, graph) = self.word_graph(input);
+    let mut position = Position::default();
+
+    input
+      .chars()
+      .zip(graph)
+      .map(|(ch, words)| {
+        let tokens = words
+          .into_iter()
+          .map(|word| self.make_token(word, position))
+          .collect();
+
+        position = position.advance(ch.encode_utf8(&mut [0; 4]));
+        tokens
+      })
+      .collect()
+  }
+
+  /// Returns up to `limit` segmentations of `input`, ordered from most to least
+  /// probable.
+  pub fn segmentations(
+    &self,
+    input: &str,
+    frequency_dict: &FrequencyDictionary,
+    limit: usize,
+  ) -> Vec<Segmentation> {
+    self
+      .segment(input, frequency_dict, limit)
+      .into_iter()
+      .map(|(words, score)| Segmentation {
+        tokens: self.make_tokens(words.into_iter()),
+        score,
+      })
+      .collect()
+  }
+
+  /// Finds the `limit` most probable segmentations of `input` with a k-best
+  /// variant of the Viterbi algorithm over the lattice of all words, where
+  /// each word is penalized by [`TOKEN_PENALTY`].
+  fn segment<'b>(
+    &self,
+    input: &'b str,
+    frequency_dict: &FrequencyDictionary,
+    limit: usize,
+  ) -> Vec<(Vec<&'b str>, f32)> {
+    if input.is_empty() || limit == 0 {
+      return vec![];
+    }
+
+    let (offsets, graph) = self.word_graph(input);
+
+    // For each position, the best scores so far, each with the position the
+    // last word starts at and the rank of the path leading there
+
+    let mut best = vec![vec![]; offsets.len()];
+
+    best[0].push((0.0, 0, 0));
+
+    for (i, &offset) in offsets.iter().enumerate() {
+      // All paths to `i` are known at this point since words only go forward.
+      // Ties go to the path with the shorter last word, so that longer words
+      // come first like with greedy tokenization.
+
+      best[i].sort_by(|a: &(f32, usize, usize), b| {
+        b.0.total_cmp(&a.0).then(b.1.cmp(&a.1))
+      });
+      best[i].truncate(limit);
+
+      for word in graph[i].iter() {
+        if let Ok(j) = offsets.binary_search(&(offset + word.len())) {
+          // Scores are kept relative to the character estimate, which sums
+          // up to the same value for every segmentation, so that words
+          // without data tie exactly
+
+          let log_probability = frequency_dict.log_probability(word)
+            - frequency_dict.character_log_probability(word)
+            - TOKEN_PENALTY / 2_f32.powi(word.chars().count() as i32 - 1);
+
+          for rank in 0..best[i].len() {
+            let score = best[i][rank].0 + log_probability;
+
+            best[j].push((score, i, rank));
+          }
+        }
+      }
+    }
+
+    let last = offsets.len() - 1;
+    let base = frequency_dict.character_log_probability(input);
+
+    (0..best[last].len())
+      .map(|rank| {
+        let mut words = vec![];
+        let (mut j, mut rank_j) = (last, rank);
+
+        while j > 0 {
+          let (_, i, rank_i) = best[j][rank_j];
+
+          words.push(&input[offsets[i]..offsets[j]]);
+          (j, rank_j) = (i, rank_i);
+        }
+
+        words.reverse();
+        (words, base + best[last][rank].0)
+      })
+      .collect()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use once_cell::sync::Lazy;
+
+  use super::{
+    DictionaryType, HomophoneMode, Span, SpanUnit, TokenKind, TokenizeMode,
+    WordDictionary, WordEntry,
+  };
+  use crate::{
+    DictionarySource, FrequencyDictionary, OverlayMode, ParseErrorKind,
+    PinyinFormat, PinyinQuery, Tone, WordPattern, BASE_SOURCE,
+  };
+
+  static CEDICT_DATA: Lazy<WordDictionary> = Lazy::new(|| {
+    WordDictionary::new(include_str!(
+      "../../../data/cedict_1_0_ts_utf-8_mdbg.txt"
+    ))
+  });
+
+  // Character frequencies as loaded by the worker
+
+  static CHARACTER_FREQUENCY_DATA: Lazy<FrequencyDictionary> =
+    Lazy::new(|| {
+      FrequencyDictionary::new(include_str!("../../../data/SUBTLEX-CH-CHR.txt"))
+    });
+
+  // Excerpt of word frequencies in the format of SUBTLEX-CH-WF
+
+  static WORD_FREQUENCY_DATA: Lazy<FrequencyDictionary> = Lazy::new(|| {
+    FrequencyDictionary::new(
+      "\"Total word count: 33,546,516\"
+\"Context number: 6,243\"
+Word\tWCount\tW/million\tlogW\tW-CD\tW-CD%\tlogW-CD
+的\t1512022\t45072.34\t6.1796\t6243\t100\t3.7954
+我\t1118765\t33349.88\t6.0487\t6242\t99.98\t3.7953
+是\t897617\t26757.57\t5.9531\t6243\t100\t3.7954
+和\t63578\t1895.23\t4.8033\t5720\t91.62\t3.7574
+我们\t184567\t5501.87\t5.2661\t6143\t98.4\t3.7884
+床\t4716\t140.58\t3.6736\t1592\t25.5\t3.2019
+桌子\t1786\t53.24\t3.2519\t1003\t16.07\t3.0013
+纸\t1520\t45.31\t3.1818\t880\t14.1\t2.9445
+发生\t9153\t272.85\t3.9616\t2687\t43.04\t3.4293
+头发\t2079\t61.97\t3.3178\t1093\t17.51\t3.0386
+发展\t1370\t40.84\t3.1367\t706\t11.31\t2.8488
+干净\t1838\t54.79\t3.2643\t1234\t19.77\t3.0913
+干部\t478\t14.25\t2.6794\t233\t3.73\t2.3674
+中国\t10588\t315.62\t4.0248\t1820\t29.15\t3.2601
+中国人\t3283\t97.87\t3.5163\t965\t15.46\t2.9845
+结婚\t18402\t548.55\t4.2649\t2967\t47.52\t3.4723
+研究\t6059\t180.61\t3.7824\t1653\t26.48\t3.2183
+生命\t7302\t217.67\t3.8634\t2021\t32.37\t3.3056
+命\t6240\t186.01\t3.7952\t1949\t31.22\t3.2898
+光\t7423\t221.28\t3.8706\t2814\t45.07\t3.4493
+双\t2154\t64.21\t3.3332\t1326\t21.24\t3.1225
+研究生\t279\t8.32\t2.4456\t209\t3.35\t2.3222
+起源\t180\t5.37\t2.2553\t120\t1.92\t2.0792
+尚未\t447\t13.32\t2.6503\t404\t6.47\t2.6064
+和尚\t1008\t30.05\t3.0035\t299\t4.79\t2.4757
+未\t1064\t31.72\t3.0269\t700\t11.21\t2.8451",
+    )
+  });
+
+  #[test]
+  fn can_get_word_entry() {
+    let data = CEDICT_DATA.get("识字", DictionaryType::Simplified).unwrap();
+
+    assert_eq!(data.len(), 1);
+    assert_eq!(&*data[0].simplified, "识字");
+    assert_eq!(&*data[0].traditional, "識字");
+  }
+
+  #[test]
+  fn can_get_all_words_with_prefix() {
+    let data = CEDICT_DATA
+      .iter_prefix("中国", DictionaryType::Simplified)
+      .collect::<Vec<_>>();
+
+    assert!(data.len() > 2);
+  }
+
+  #[test]
+  fn should_iterate_words_with_prefix_in_order() {
+    let words = CEDICT_DATA
+      .iter_words_with_prefix("中国", Some(1), DictionaryType::Simplified)
+      .map(|(word, _)| word)
+      .collect::<Vec<_>>();
+    let mut sorted = words.clone();
+
+    sorted.sort();
+
+    assert_eq!(words.first().map(|word| &**word), Some("中国"));
+    assert!(words.iter().all(|word| word.chars().count() <= 3));
+    assert_eq!(words, sorted);
+  }
+
+  #[test]
+  fn can_get_all_words_prefixing_text() {
+    let data = CEDICT_DATA
+      .iter_common_prefixes("中国人民", DictionaryType::Simplified)
+      .map(|(word, _)| word)
+      .collect::<Vec<_>>();
+
+    assert_eq!(data, vec!["中", "中国", "中国人"]);
+  }
+
+  #[test]
+  fn can_get_words_by_pinyin() {
+    let data = CEDICT_DATA.get_by_pinyin("zhong1 guo2").unwrap();
+
+    assert!(data.iter().any(|entry| &*entry.simplified == "中国"));
+    assert!(CEDICT_DATA
+      .iter_pinyin_prefix("Zhong1 guo2")
+      .any(|entry| &*entry.simplified == "中国人"));
+    assert!(CEDICT_DATA
+      .iter_pinyin_prefix("zhong1 guo2")
+      .all(|entry| entry.pinyin.to_lowercase().starts_with("zhong1 guo2")));
+  }
+
+  #[test]
+  fn can_get_homophones() {
+    let homophones = CEDICT_DATA
+      .iter_homophones(
+        "绿",
+        DictionaryType::Simplified,
+        HomophoneMode::ToneInsensitive,
+      )
+      .map(|(entry, mode)| (&*entry.simplified, mode))
+      .collect::<Vec<_>>();
+
+    assert!(homophones.contains(&("律", HomophoneMode::Exact)));
+    assert!(homophones.contains(&("旅", HomophoneMode::ToneInsensitive)));
+    assert!(!homophones
+      .iter()
+      .any(|&(word, _)| word == "路" || word == "绿"));
+
+    let homophones = CEDICT_DATA
+      .iter_homophones("绿", DictionaryType::Simplified, HomophoneMode::Exact)
+      .map(|(entry, _)| &*entry.simplified)
+      .collect::<Vec<_>>();
+
+    assert_eq!(homophones, vec!["律"]);
+  }
+
+  #[test]
+  fn should_only_skip_variant_homophones() {
+    let dict = WordDictionary::new(
+      "綠 绿 [lu:4] /green/
+律 律 [lu:4] /law/
+慮 虑 [lu:4] /a variant of rock music/
+濾 滤 [lu:4] /variant of 律[lu:4]/old variant of 綠|绿[lu:4]/
+",
+    );
+    let homophones = dict
+      .iter_homophones("绿", DictionaryType::Simplified, HomophoneMode::Exact)
+      .map(|(entry, _)| &*entry.simplified)
+      .collect::<Vec<_>>();
+
+    assert_eq!(homophones, vec!["律", "虑"]);
+  }
+
+  #[test]
+  fn can_get_confusable_homophones() {
+    let homophones = |word, mode| {
+      CEDICT_DATA
+        .iter_homophones(word, DictionaryType::Simplified, mode)
+        .map(|(entry, mode)| (&*entry.simplified, mode))
+        .collect::<Vec<_>>()
+    };
+
+    assert!(homophones("绿", HomophoneMode::Confusable)
+      .contains(&("女", HomophoneMode::Confusable)));
+    assert!(!homophones("绿", HomophoneMode::ToneInsensitive)
+      .iter()
+      .any(|&(word, _)| word == "女"));
+
+    let homophones = homophones("先", HomophoneMode::Confusable);
+
+    assert!(homophones.contains(&("鲜", HomophoneMode::Exact)));
+    assert!(homophones.contains(&("县", HomophoneMode::ToneInsensitive)));
+    assert!(homophones.contains(&("项", HomophoneMode::Confusable)));
+  }
+
+  #[test]
+  fn can_query_pinyin() {
+    let query = |query| {
+      CEDICT_DATA
+        .query_pinyin(&query, &WORD_FREQUENCY_DATA)
+        .into_iter()
+        .map(|entry| &*entry.simplified)
+        .collect::<Vec<_>>()
+    };
+
+    let rhymes = query(PinyinQuery {
+      final_: Some("uang"),
+      tone: Some(Tone::First),
+      ..Default::default()
+    });
+
+    assert_eq!(&rhymes[..2], &["光", "双"]);
+    assert!(rhymes.contains(&"窗"));
+    assert!(!rhymes.contains(&"黄"));
+
+    let words = query(PinyinQuery {
+      tones: PinyinQuery::parse_tones("3-4"),
+      ..Default::default()
+    });
+
+    assert!(words.contains(&"以后"));
+    assert!(words.contains(&"北大"));
+    assert!(!words.contains(&"光"));
+
+    let characters = query(PinyinQuery {
+      final_: Some("ian"),
+      syllables: Some(1),
+      ..Default::default()
+    });
+
+    assert!(characters.contains(&"先"));
+    assert!(characters.contains(&"线"));
+    assert!(!characters.contains(&"见面"));
+  }
+
+  #[test]
+  fn should_only_skip_variant_entries_in_pinyin_queries() {
+    let dict = WordDictionary::new(
+      "綠 绿 [lu:4] /green/
+慮 虑 [lu:4] /a variant of rock music/
+濾 滤 [lu:4] /variant of 綠|绿[lu:4]/
+",
+    );
+    let mut words = dict
+      .query_pinyin(
+        &PinyinQuery {
+          syllables: Some(1),
+          ..Default::default()
+        },
+        &WORD_FREQUENCY_DATA,
+      )
+      .into_iter()
+      .map(|entry| &*entry.simplified)
+      .collect::<Vec<_>>();
+
+    words.sort();
+
+    assert_eq!(words, vec!["绿", "虑"]);
+  }
+
+  #[test]
+  fn can_format_pinyin_of_entries() {
+    let entry =
+      &CEDICT_DATA.get("西安", DictionaryType::Simplified).unwrap()[0];
+
+    assert_eq!(
+      &*entry.with_pinyin_format(PinyinFormat::ToneMarks).pinyin,
+      "Xī'ān"
+    );
+    assert_eq!(
+      &*entry.with_pinyin_format(PinyinFormat::Zhuyin).pinyin,
+      "ㄒㄧ ㄢ"
+    );
+    assert_eq!(
+      entry.with_pinyin_format(PinyinFormat::Numbered).pinyin,
+      entry.pinyin
+    );
+  }
+
+  #[test]
+  fn can_search_by_pinyin() {
+    let search = |query| {
+      CEDICT_DATA
+        .search_pinyin(query, &WORD_FREQUENCY_DATA)
+        .into_iter()
+        .map(|entry| entry.simplified.to_string())
+        .collect::<Vec<_>>()
+    };
+
+    for query in [
+      "zhongguo",
+      "zhong1guo2",
+      "zhong guo",
+      "Zhong1 Guo2",
+      "zhongg",
+    ] {
+      assert!(search(query).contains(&"中国".to_string()), "{query}");
+    }
+
+    assert_eq!(search("zg").first().map(|word| &**word), Some("中国"));
+    assert!(!search("zhong4guo2").contains(&"中国".to_string()));
+
+    let result = search("xian");
+
+    assert!(result.contains(&"西安".to_string()));
+    assert!(result.contains(&"先".to_string()));
+
+    let result = search("xi'an");
+
+    assert!(result.contains(&"西安".to_string()));
+    assert!(!result.contains(&"先".to_string()));
+
+    assert!(search("").is_empty());
+    assert!(search("qqqq").is_empty());
+  }
+
+  #[test]
+  fn can_get_multiple_word_entries() {
+    let data = CEDICT_DATA.get("沈", DictionaryType::Simplified).unwrap();
+
+    assert_eq!(data.len(), 4);
+  }
+
+  #[test]
+  fn can_get_words_matching_pattern() {
+    let words = CEDICT_DATA
+      .iter_matching(
+        &WordPattern::parse("中?人").unwrap(),
+        DictionaryType::Simplified,
+        |_, _| false,
+      )
+      .map(|entry| &*entry.simplified)
+      .collect::<Vec<_>>();
+
+    assert!(words.contains(&"中国人"));
+    assert!(words.iter().all(|word| word.chars().count() == 3
+      && word.starts_with('中')
+      && word.ends_with('人')));
+
+    let words = CEDICT_DATA
+      .iter_matching(
+        &WordPattern::parse("*子").unwrap(),
+        DictionaryType::Simplified,
+        |_, _| false,
+      )
+      .map(|entry| &*entry.simplified)
+      .collect::<Vec<_>>();
+
+    assert!(words.contains(&"桌子"));
+    assert!(words.iter().all(|word| word.ends_with('子')));
+  }
+
+  #[test]
+  fn can_suggest_words_for_typos() {
+    assert_eq!(CEDICT_DATA.get("中囯人", DictionaryType::Simplified), None);
+
+    let suggestions =
+      CEDICT_DATA.get_suggestions("中囯人", 1, DictionaryType::Simplified);
+
+    assert!(suggestions
+      .iter()
+      .any(
+        |(entry, distance)| &*entry.simplified == "中国人" && *distance == 1
+      ));
+    assert!(suggestions.iter().all(|&(_, distance)| distance == 1));
+  }
+
+  #[test]
+  fn can_add_edit_and_remove_words() {
+    let mut dict = WordDictionary::new(
+      "發 发 [fa1] /to send out/\n髮 发 [fa4] /hair/\n頭髮 头发 [tou2 fa5] /hair/",
+    );
+    let entry = WordEntry {
+      traditional: "頭髮".into(),
+      simplified: "头发".into(),
+      pinyin: "tou2 fa5".into(),
+      jyutping: None,
+      english: "hair (on the head)".into(),
+      source: BASE_SOURCE.into(),
+    };
+
+    assert!(dict.insert_entry(entry.clone()).is_some());
+    assert_eq!(
+      dict.get("頭髮", DictionaryType::Traditional),
+      Some(&vec![entry.clone()])
+    );
+    assert_eq!(dict.remove_entry(&entry), Some(entry));
+    assert_eq!(dict.get("头发", DictionaryType::Simplified), None);
+    assert_eq!(
+      dict.iter_prefix("頭", DictionaryType::Traditional).count(),
+      0
+    );
+
+    assert_eq!(dict.remove_word("发", DictionaryType::Simplified).len(), 2);
+    assert_eq!(dict.get("發", DictionaryType::Traditional), None);
+    assert_eq!(dict.get("髮", DictionaryType::Traditional), None);
+    assert_eq!(dict.iter().count(), 0);
+  }
+
+  #[test]
+  fn can_overlay_sources() {
+    let mut dict = WordDictionary::new(
+      "發 发 [fa1] /to send out/\n髮 发 [fa4] /hair/\n頭 头 [tou2] /head/\n頭髮 头发 [tou2 fa5] /hair/",
+    );
+    fn entries<'a>(
+      dict: &'a WordDictionary,
+      word: &str,
+    ) -> Vec<(&'a str, &'a str, &'a str)> {
+      dict
+        .get(word, DictionaryType::Simplified)
+        .into_iter()
+        .flatten()
+        .map(|entry| (&*entry.pinyin, &*entry.english, &*entry.source))
+        .collect()
+    }
+
+    let report = dict.add_source(
+      "頭髮 头发 [tou2 fa5] /hair (on the head)/\n頭髮 头发 [tou2 fa4] /hairdo/",
+      DictionarySource::new("glossary", 1, OverlayMode::Append),
+    );
+
+    assert_eq!(report.entries, 2);
+    assert_eq!(
+      entries(&dict, "头发"),
+      vec![
+        ("tou2 fa5", "hair (on the head)", "glossary"),
+        ("tou2 fa4", "hairdo", "glossary"),
+      ]
+    );
+
+    dict.add_source(
+      "發 发 [fa1] /to post (online)/",
+      DictionarySource::new("jargon", 2, OverlayMode::Shadow),
+    );
+
+    assert_eq!(
+      entries(&dict, "发"),
+      vec![
+        ("fa4", "hair", BASE_SOURCE),
+        ("fa1", "to post (online)", "jargon"),
+      ]
+    );
+
+    // Sources added later still lose against sources of higher priority
+
+    let report = dict.add_source(
+      "發 发 [fa1] /to emit/\n頭 头 [tou2] /top/",
+      DictionarySource::new("old", 1, OverlayMode::Shadow),
+    );
+
+    assert_eq!((report.entries, report.skipped), (1, 1));
+    assert_eq!(entries(&dict, "发")[1].2, "jargon");
+    assert_eq!(entries(&dict, "头"), vec![("tou2", "top", "old")]);
+
+    let snapshot = WordDictionary::from_snapshot(&dict.to_snapshot()).unwrap();
+
+    assert_eq!(snapshot.sources(), dict.sources());
+    assert_eq!(entries(&snapshot, "头发"), entries(&dict, "头发"));
+  }
+
+  #[test]
+  fn should_tokenize_words_of_overlays() {
+    let mut dict = CEDICT_DATA.clone();
+    let tokens = |dict: &WordDictionary| {
+      dict
+        .tokenize("头皮屑")
+        .into_iter()
+        .map(|token| token.value.to_string())
+        .collect::<Vec<_>>()
+    };
+
+    assert_ne!(tokens(&dict), vec!["头皮屑"]);
+
+    dict.add_source(
+      "頭皮屑 头皮屑 [tou2 pi2 xie4] /dandruff/",
+      DictionarySource::new("glossary", 1, OverlayMode::Append),
+    );
+
+    assert_eq!(tokens(&dict), vec!["头皮屑"]);
+  }
+
+  #[test]
+  fn can_get_classifiers_of_words() {
+    let classifiers =
+      CEDICT_DATA.get_classifiers("人", DictionaryType::Simplified);
+
+    assert_eq!(
+      classifiers
+        .iter()
+        .map(|link| (link.simplified, link.pinyin))
+        .collect::<Vec<_>>(),
+      vec![("个", Some("ge4")), ("位", Some("wei4"))]
+    );
+    assert_eq!(
+      classifiers[0].entry.as_deref().map(|entry| &*entry.english),
+      CEDICT_DATA
+        .get("个", DictionaryType::Simplified)
+        .map(|entries| &*entries[0].english)
+    );
+    assert!(CEDICT_DATA
+      .get_classifiers("中国", DictionaryType::Simplified)
+      .is_empty());
+  }
+
+  #[test]
+  fn can_get_words_using_classifier() {
+    let words = |classifier, ty| {
+      CEDICT_DATA
+        .get_classified_words(classifier, ty, &WORD_FREQUENCY_DATA)
+        .into_iter()
+        .map(|entry| &*entry.simplified)
+        .collect::<Vec<_>>()
+    };
+
+    assert_eq!(
+      words("张", DictionaryType::Simplified),
+      vec!["床", "桌子", "纸"]
+    );
+    assert_eq!(
+      words("張", DictionaryType::Traditional),
+      words("张", DictionaryType::Simplified)
+    );
+    assert!(words("張", DictionaryType::Simplified).is_empty());
+  }
+
+  #[test]
+  fn should_update_classifier_index() {
+    let mut dict = CEDICT_DATA.clone();
+    let entry = WordEntry {
+      traditional: "紙".into(),
+      simplified: "纸".into(),
+      pinyin: "zhi3".into(),
+      jyutping: None,
+      english: "paper/CL:張|张[zhang1],沓[da2]/".into(),
+      source: BASE_SOURCE.into(),
+    };
+    let classified = |dict: &WordDictionary, classifier| {
+      dict
+        .get_classified_words(
+          classifier,
+          DictionaryType::Simplified,
+          &WORD_FREQUENCY_DATA,
+        )
+        .len()
+    };
+
+    dict.remove_entry(&entry);
+    assert_eq!(classified(&dict, "张"), 2);
+    assert_eq!(classified(&dict, "沓"), 0);
+
+    dict.insert_entry(WordEntry {
+      english: "paper/CL:沓[da2]/".into(),
+      ..entry
+    });
+    assert_eq!(classified(&dict, "张"), 2);
+    assert_eq!(classified(&dict, "沓"), 1);
+
+    let snapshot = WordDictionary::from_snapshot(&dict.to_snapshot()).unwrap();
+
+    assert_eq!(classified(&snapshot, "沓"), 1);
+  }
+
+  #[test]
+  fn can_convert_between_scripts() {
+    let conversion = CEDICT_DATA.convert(
+      "头发干净, 发展",
+      DictionaryType::Traditional,
+      &WORD_FREQUENCY_DATA,
+    );
+
+    assert_eq!(conversion.text, "頭髮乾淨, 發展");
+    assert!(conversion.ambiguities.is_empty());
+
+    let conversion = CEDICT_DATA.convert(
+      "頭髮乾淨, 發展",
+      DictionaryType::Simplified,
+      &WORD_FREQUENCY_DATA,
+    );
+
+    assert_eq!(conversion.text, "头发干净, 发展");
+    assert!(conversion.ambiguities.is_empty());
+  }
+
+  #[test]
+  fn should_report_ambiguous_conversions() {
+    let conversion = CEDICT_DATA.convert(
+      "我发, 干",
+      DictionaryType::Traditional,
+      &WORD_FREQUENCY_DATA,
+    );
+
+    assert_eq!(conversion.text, "我發, 乾");
+    assert_eq!(
+      conversion
+        .ambiguities
+        .iter()
+        .map(|ambiguity| (
+          &*ambiguity.source.value,
+          ambiguity.target.char_span,
+          ambiguity
+            .candidates
+            .iter()
+            .map(|candidate| &**candidate)
+            .collect::<Vec<_>>()
+        ))
+        .collect::<Vec<_>>(),
+      vec![
+        ("发", Span { start: 1, end: 2 }, vec!["發", "髮"]),
+        ("干", Span { start: 4, end: 5 }, vec!["乾", "幹", "干"]),
+      ]
+    );
+
+    let conversion = CEDICT_DATA.convert(
+      "乾",
+      DictionaryType::Simplified,
+      &WORD_FREQUENCY_DATA,
+    );
+
+    assert_eq!(conversion.text, "干");
+    assert_eq!(conversion.ambiguities[0].candidates.len(), 2);
+
+    // 干 is 幹 in 干部 but 乾 in the more frequent 干净, without word
+    // frequencies the order of the dictionary decides
+
+    for frequency_dict in [&*WORD_FREQUENCY_DATA, &*CHARACTER_FREQUENCY_DATA] {
+      let conversion = CEDICT_DATA.convert(
+        "一个发干",
+        DictionaryType::Traditional,
+        frequency_dict,
+      );
+
+      assert_eq!(conversion.text, "一個發乾");
+    }
+  }
+
+  #[test]
+  fn should_report_malformed_lines() {
+    let (dict, report) = WordDictionary::parse_with_report(
+      "# CC-CEDICT
+中國 中国 [Zhong1 guo2] /China/
+中國
+中國 中国 Zhong1 guo2 /China/
+中國 中国 [Zhong1 guo2
+中國 中国 [Zhong1 guo2] China
+中國 中国 [Zhong1 guo2] //
+中國 中国 []
+ABC ABC [A B C] /alphabet/
+
+人 人 [ren2] /person/",
+    );
+
+    assert_eq!(dict.iter().count(), 2);
+    assert_eq!(report.entries, 2);
+    assert_eq!(report.skipped, 7);
+    assert_eq!(
+      report
+        .errors
+        .iter()
+        .map(|err| (err.line, err.kind.clone()))
+        .collect::<Vec<_>>(),
+      vec![
+        (3, ParseErrorKind::MissingField("simplified")),
+        (4, ParseErrorKind::InvalidPinyin),
+        (5, ParseErrorKind::InvalidPinyin),
+        (6, ParseErrorKind::InvalidDefinition),
+        (7, ParseErrorKind::EmptyDefinition),
+        (8, ParseErrorKind::InvalidPinyin),
+      ]
+    );
+    assert_eq!(report.errors[0].to_string(), "line 3: missing simplified");
+  }
+
+  #[test]
+  fn should_round_trip_snapshot() {
+    let dict =
+      WordDictionary::from_snapshot(&CEDICT_DATA.to_snapshot()).unwrap();
+
+    assert!(dict.iter().eq(CEDICT_DATA.iter()));
+    assert_eq!(
+      dict.get("中国", DictionaryType::Simplified),
+      CEDICT_DATA.get("中国", DictionaryType::Simplified)
+    );
+
+    // Indices are read as they are instead of being rebuilt
+
+    assert!(dict.get_by_pinyin("zhong1 guo2").is_some());
+    assert_eq!(
+      dict.get_by_pinyin("zhong1 guo2"),
+      CEDICT_DATA.get_by_pinyin("zhong1 guo2")
+    );
+    assert_eq!(
+      dict.get_classified_words(
+        "个",
+        DictionaryType::Simplified,
+        &WORD_FREQUENCY_DATA
+      ),
+      CEDICT_DATA.get_classified_words(
+        "个",
+        DictionaryType::Simplified,
+        &WORD_FREQUENCY_DATA
+      )
+    );
+    assert!(dict
+      .iter_homophones(
+        "先",
+        DictionaryType::Simplified,
+        HomophoneMode::Confusable
+      )
+      .eq(CEDICT_DATA.iter_homophones(
+        "先",
+        DictionaryType::Simplified,
+        HomophoneMode::Confusable
+      )));
+    assert_eq!(
+      dict.convert(
+        "我发, 干",
+        DictionaryType::Traditional,
+        &WORD_FREQUENCY_DATA
+      ),
+      CEDICT_DATA.convert(
+        "我发, 干",
+        DictionaryType::Traditional,
+        &WORD_FREQUENCY_DATA
+      )
+    );
+  }
+
+  #[test]
+  fn can_parse_jyutping() {
+    let (dict, report) = WordDictionary::parse_with_report(
+      "中國 中国 [Zhong1 guo2] {zung1 gwok3} /China/
+人 人 [ren2] {jan4 /person/
+你 你 [ni3] {} /you/",
+    );
+    let entry = &dict.get("中国", DictionaryType::Simplified).unwrap()[0];
+
+    assert_eq!(entry.jyutping.as_deref(), Some("zung1 gwok3"));
+    assert_eq!(&*entry.english, "China");
+    assert_eq!(
+      report
+        .errors
+        .iter()
+        .map(|err| (err.line, err.kind.clone()))
+        .collect::<Vec<_>>(),
+      vec![
+        (2, ParseErrorKind::InvalidJyutping),
+        (3, ParseErrorKind::InvalidJyutping),
+      ]
+    );
+  }
+
+  #[test]
+  fn can_merge_jyutping_readings() {
+    let mut dict = CEDICT_DATA.clone();
+    let report = dict.merge_jyutping(
+      "# CC-Canto readings
+中國 中国 [Zhong1 guo2] {zung1 gwok3}
+你好 你好 [ni3 hao3] {nei5 hou2}
+你 你 [ni3] {nei5}
+你 你 [ni3]
+無 无 [wu2] {mou4}",
+    );
+
+    assert_eq!((report.entries, report.skipped), (3, 2));
+    assert_eq!(
+      report.errors[0].kind,
+      ParseErrorKind::MissingField("jyutping")
+    );
+
+    let entries = dict.get("中国", DictionaryType::Simplified).unwrap();
+
+    assert_eq!(entries[0].jyutping.as_deref(), Some("zung1 gwok3"));
+    assert_eq!(entries[1].jyutping, None);
+    assert_eq!(
+      dict.get_by_jyutping("Zung1 Gwok3"),
+      Some(&vec![entries[0].clone()])
+    );
+    assert_eq!(
+      dict
+        .iter_jyutping_prefix("nei5")
+        .map(|entry| &*entry.simplified)
+        .collect::<Vec<_>>(),
+      vec!["你", "你好"]
+    );
+    assert_eq!(
+      dict
+        .search_jyutping("nh", &WORD_FREQUENCY_DATA)
+        .into_iter()
+        .map(|entry| &*entry.simplified)
+        .collect::<Vec<_>>(),
+      vec!["你好"]
+    );
+
+    let snapshot = WordDictionary::from_snapshot(&dict.to_snapshot()).unwrap();
+
+    assert!(snapshot.iter().eq(dict.iter()));
+    assert_eq!(
+      snapshot.get_by_jyutping("zung1 gwok3"),
+      dict.get_by_jyutping("zung1 gwok3")
+    );
+
+    dict.remove_entry(&entries[0].clone());
+    assert_eq!(dict.get_by_jyutping("zung1 gwok3"), None);
+  }
+
+  #[test]
+  fn can_find_jyutping_homophones() {
+    let dict = WordDictionary::new(
+      "你 你 [ni3] {nei5} /you/
+李 李 [Li3] {lei5} /surname Li/
+理 理 [li3] {lei5} /reason/
+尼 尼 [ni2] {nei4} /Buddhist nun/
+好 好 [hao3] {hou2} /good/",
+    );
+    let homophones = |word, mode| {
+      dict
+        .iter_jyutping_homophones(word, DictionaryType::Simplified, mode)
+        .map(|(entry, mode)| (&*entry.simplified, mode))
+        .collect::<Vec<_>>()
+    };
+
+    assert_eq!(
+      homophones("李", HomophoneMode::Exact),
+      vec![("理", HomophoneMode::Exact)]
+    );
+    assert_eq!(
+      homophones("你", HomophoneMode::ToneInsensitive),
+      vec![("尼", HomophoneMode::ToneInsensitive)]
+    );
+    assert_eq!(
+      homophones("你", HomophoneMode::Confusable),
+      vec![
+        ("尼", HomophoneMode::ToneInsensitive),
+        ("李", HomophoneMode::Confusable),
+        ("理", HomophoneMode::Confusable),
+      ]
+    );
+  }
+
+  #[test]
+  fn should_tokenize_simple_sentence() {
+    let tokens = CEDICT_DATA.tokenize("我是中国人。");
+
+    assert_eq!(
+      tokens.iter().map(|token| &*token.value).collect::<Vec<_>>(),
+      vec!["我", "是", "中国人", "。"]
+    );
+  }
+
+  #[test]
+  fn should_classify_characters() {
+    for (chars, kind) in [
+      ("我中國〇々𠀀", TokenKind::UnknownHanzi),
+      (
+        "。，、；：？！“”‘’（）《》「」『』【】〔〕…—·・｡･︐﹁﹏.,!?",
+        TokenKind::Punctuation,
+      ),
+      (" \t\n\u{3000}", TokenKind::Whitespace),
+      ("aZéＡｚ", TokenKind::Latin),
+      ("09０９", TokenKind::Number),
+      ("$+|~＄＋｜￥→", TokenKind::Symbol),
+    ] {
+      for ch in chars.chars() {
+        assert_eq!(TokenKind::of_char(ch), kind, "{ch:?}");
+      }
+    }
+  }
+
+  #[test]
+  fn should_classify_tokens() {
+    let tokens = CEDICT_DATA.tokenize("我有3.14个David，/𠀀 | 2024年！");
+
+    assert_eq!(
+      tokens
+        .iter()
+        .map(|token| (&*token.value, token.kind))
+        .collect::<Vec<_>>(),
+      vec![
+        ("我", TokenKind::ChineseWord),
+        ("有", TokenKind::ChineseWord),
+        ("3.14", TokenKind::Number),
+        ("个", TokenKind::ChineseWord),
+        ("David", TokenKind::Latin),
+        ("，", TokenKind::Punctuation),
+        ("/", TokenKind::Punctuation),
+        ("𠀀", TokenKind::UnknownHanzi),
+        (" ", TokenKind::Whitespace),
+        ("|", TokenKind::Symbol),
+        (" ", TokenKind::Whitespace),
+        ("2024", TokenKind::Number),
+        ("年", TokenKind::ChineseWord),
+        ("！", TokenKind::Punctuation),
+      ]
+    );
+  }
+
+  #[test]
+  fn should_compute_spans_in_all_units() {
+    let input = "𠀀😀中国 ok";
+    let tokens = CEDICT_DATA.tokenize(input);
+    let spans = |unit| {
+      tokens
+        .iter()
+        .map(|token| {
+          let Span { start, end } = token.span(unit);
+          (start, end)
+        })
+        .collect::<Vec<_>>()
+    };
+
+    assert_eq!(
+      tokens.iter().map(|token| &*token.value).collect::<Vec<_>>(),
+      vec!["𠀀", "😀", "中国", " ", "ok"]
+    );
+    assert_eq!(
+      spans(SpanUnit::Byte),
+      vec![(0, 4), (4, 8), (8, 14), (14, 15), (15, 17)]
+    );
+    assert_eq!(
+      spans(SpanUnit::Char),
+      vec![(0, 1), (1, 2), (2, 4), (4, 5), (5, 7)]
+    );
+    assert_eq!(
+      spans(SpanUnit::Utf16),
+      vec![(0, 2), (2, 4), (4, 6), (6, 7), (7, 9)]
+    );
+
+    let utf16 = input.encode_utf16().collect::<Vec<_>>();
+
+    for token in tokens.iter() {
+      let Span { start, end } = token.utf16_span;
+
+      assert_eq!(
+        &input[token.byte_span.start..token.byte_span.end],
+        &*token.value
+      );
+      assert_eq!(String::from_utf16_lossy(&utf16[start..end]), &*token.value);
+    }
+  }
+
+  #[test]
+  fn should_compute_spans_in_lattice() {
+    let lattice = CEDICT_DATA.lattice("😀中国");
+
+    assert!(lattice[1].iter().all(|token| token.char_span.start == 1
+      && token.utf16_span.start == 2
+      && token.byte_span.start == 4));
+    assert!(lattice[1]
+      .iter()
+      .any(|token| token.utf16_span == Span { start: 2, end: 4 }));
+  }
+
+  #[test]
+  fn should_resolve_ambiguities_statistically() {
+    let tokenize = |input, mode| {
+      CEDICT_DATA
+        .tokenize_with_mode(input, mode)
+        .into_iter()
+        .map(|token| token.value.to_string())
+        .collect::<Vec<_>>()
+    };
+    let statistical = TokenizeMode::Statistical(&WORD_FREQUENCY_DATA);
+
+    assert_eq!(
+      tokenize("研究生命的起源", TokenizeMode::Greedy),
+      vec!["研究生", "命", "的", "起源"]
+    );
+    assert_eq!(
+      tokenize("研究生命的起源", statistical),
+      vec!["研究", "生命", "的", "起源"]
+    );
+    assert_eq!(
+      tokenize("结婚的和尚未结婚的", TokenizeMode::Greedy),
+      vec!["结婚", "的", "和尚", "未", "结婚", "的"]
+    );
+    assert_eq!(
+      tokenize("结婚的和尚未结婚的", statistical),
+      vec!["结婚", "的", "和", "尚未", "结婚", "的"]
+    );
+  }
+
+  #[test]
+  fn should_prefer_words_with_character_frequencies() {
+    let segment = |input| {
+      CEDICT_DATA
+        .segmentations(input, &CHARACTER_FREQUENCY_DATA, 3)
+        .into_iter()
+        .map(|segmentation| {
+          let words = segmentation
+            .tokens
+            .iter()
+            .map(|token| token.value.to_string())
+            .collect::<Vec<_>>();
+
+          (words, segmentation.score)
+        })
+        .collect::<Vec<_>>()
+    };
+
+    // Characters alone can't tell 我们|的 and 我|们的 apart
+
+    let segmentations = segment("我们的");
+
+    assert_eq!(segmentations[0].0, vec!["我们", "的"]);
+    assert_eq!(segmentations[1].0, vec!["我", "们的"]);
+    assert_eq!(segmentations[0].1, segmentations[1].1);
+    assert_eq!(segmentations[2].0, vec!["我", "们", "的"]);
+    assert!(segmentations[1].1 > segmentations[2].1);
+    assert_eq!(
+      CEDICT_DATA.tokenize_with_mode(
+        "我们的",
+        TokenizeMode::Statistical(&CHARACTER_FREQUENCY_DATA)
+      ),
+      CEDICT_DATA.tokenize("我们的")
+    );
+
+    let segmentations = segment("研究生命起源");
+
+    assert_eq!(segmentations[0].0, vec!["研究", "生命", "起源"]);
+    assert!(segmentations[0].1 > segmentations[1].1);
+  }
+
+  #[test]
+  fn should_tokenize_statistically_like_greedy_if_unambiguous() {
+    for input in ["我是中国人。", "我的名字叫David。", "Hello 世界"]
+    {
+      let tokens = CEDICT_DATA.tokenize_with_mode(
+        input,
+        TokenizeMode::Statistical(&WORD_FREQUENCY_DATA),
+      );
+
+      assert_eq!(tokens, CEDICT_DATA.tokenize(input));
+    }
+  }
+
+  #[test]
+  fn can_get_lattice_of_all_words() {
+    let lattice = CEDICT_DATA.lattice("研究生命");
+    let words_at = |offset: usize| {
+      lattice[offset]
+        .iter()
+        .map(|token| token.value.to_string())
+        .collect::<Vec<_>>()
+    };
+
+    assert_eq!(lattice.len(), 4);
+    assert!(lattice.iter().flatten().all(|token| token.has_entries));
+    assert!(lattice[2].iter().all(|token| token.char_span.start == 2));
+
+    for (offset, word) in [
+      (0, "研"),
+      (0, "研究"),
+      (0, "研究生"),
+      (2, "生"),
+      (2, "生命"),
+      (3, "命"),
+    ] {
+      assert!(words_at(offset).contains(&word.to_string()));
+    }
+
+    let lattice = CEDICT_DATA.lattice("Hi 你");
+
+    assert_eq!(lattice[0][0].value.as_ref(), "Hi");
+    assert!(lattice[1].is_empty());
+    assert_eq!(lattice[2][0].value.as_ref(), " ");
+    assert_eq!(lattice[3][0].value.as_ref(), "你");
+  }
+
+  #[test]
+  fn can_get_alternative_segmentations() {
+    let segmentations =
+      CEDICT_DATA.segmentations("研究生命", &WORD_FREQUENCY_DATA, 3);
+    let words = segmentations
+      .iter()
+      .map(|segmentation| {
+        segmentation
+          .tokens
+          .iter()
+          .map(|token| token.value.to_string())
+          .collect::<Vec<_>>()
+      })
+      .collect::<Vec<_>>();
+
+    assert_eq!(segmentations.len(), 3);
+    assert_eq!(words[0], vec!["研究", "生命"]);
+    assert!(words.contains(&vec!["研究生".to_string(), "命".to_string()]));
+    assert!(segmentations
+      .windows(2)
+      .all(|window| window[0].score >= window[1].score));
+    assert_eq!(
+      segmentations[0].tokens,
+      CEDICT_DATA.tokenize_with_mode(
+        "研究生命",
+        TokenizeMode::Statistical(&WORD_FREQUENCY_DATA)
+      )
     );
   }
 